@@ -0,0 +1,258 @@
+use crate::position::{Direction, Pos};
+
+/// One bit for every position on a board, packed so that set operations on whole boards are
+/// fast. Positions are stored row by row, starting from the bottom-left corner.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitBoard<const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+    words: Vec<u64>
+}
+
+impl<const W: usize, const H: usize> BitBoard<W, H> {
+
+    /// Creates a new bitboard with no bits set.
+    pub fn new() -> BitBoard<W, H> {
+        BitBoard { words: vec![0; (W * H).div_ceil(u64::BITS as usize)] }
+    }
+
+    /// Checks whether the bit for a position is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    pub fn get(&self, pos: Pos<W, H>) -> bool {
+        let (word, bit) = BitBoard::bit_pos(pos);
+        self.words[word] & (1 << bit) != 0
+    }
+
+    /// Sets or clears the bit for a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to change
+    /// * `is_set` - whether the bit should be set
+    pub fn set(&mut self, pos: Pos<W, H>, is_set: bool) {
+        let (word, bit) = BitBoard::bit_pos(pos);
+        match is_set {
+            true => self.words[word] |= 1 << bit,
+            false => self.words[word] &= !(1 << bit)
+        }
+    }
+
+    /// Counts how many bits are set.
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Checks whether no bits are set.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// Returns an iterator of every position whose bit is set, row by row from the bottom of
+    /// the board up.
+    pub fn iter(&self) -> impl Iterator<Item=Pos<W, H>> + '_ {
+        (0..H).flat_map(|y| (0..W).map(move |x| Pos::new(x, y)))
+            .filter(|&pos| self.get(pos))
+    }
+
+    /// Creates a bitboard with only the bits set in both this bitboard and another.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the other bitboard to combine with this one
+    pub fn and(&self, other: &BitBoard<W, H>) -> BitBoard<W, H> {
+        let words = self.words.iter()
+            .zip(other.words.iter())
+            .map(|(&first, &second)| first & second)
+            .collect();
+
+        BitBoard { words }
+    }
+
+    /// Creates a bitboard with the bits set in either this bitboard or another.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the other bitboard to combine with this one
+    pub fn or(&self, other: &BitBoard<W, H>) -> BitBoard<W, H> {
+        let words = self.words.iter()
+            .zip(other.words.iter())
+            .map(|(&first, &second)| first | second)
+            .collect();
+
+        BitBoard { words }
+    }
+
+    /// Creates a bitboard with every bit moved one position in the given direction. Bits moved
+    /// past the edge of the board are dropped, and the row or column left behind is cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - the direction to move every bit
+    pub fn shift(&self, dir: Direction) -> BitBoard<W, H> {
+        let mut shifted = match dir {
+            Direction::North => self.shift_bits_up(W),
+            Direction::South => self.shift_bits_down(W),
+            Direction::East => self.shift_bits_up(1),
+            Direction::West => self.shift_bits_down(1)
+        };
+
+        // Bits that moved off one side of a row wrap into the next row, so they are removed
+        let wrapped_x = match dir {
+            Direction::East => Some(0),
+            Direction::West => W.checked_sub(1),
+            _ => None
+        };
+        if let Some(x) = wrapped_x {
+            for y in 0..H {
+                shifted.set(Pos::new(x, y), false);
+            }
+        }
+
+        shifted
+    }
+
+    /// Moves every bit toward higher positions by the given number of bits, dropping the bits
+    /// moved past the last position.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - the number of bits to move by
+    fn shift_bits_up(&self, amount: usize) -> BitBoard<W, H> {
+        let bits = u64::BITS as usize;
+        let (word_shift, bit_shift) = (amount / bits, (amount % bits) as u32);
+
+        let mut words: Vec<u64> = (0..self.words.len()).map(|index| {
+            let source = index.checked_sub(word_shift);
+            let high = source.map(|source| self.words[source] << bit_shift).unwrap_or(0);
+            let low = match bit_shift {
+                0 => 0,
+                _ => source.and_then(|source| source.checked_sub(1))
+                    .map(|source| self.words[source] >> (u64::BITS - bit_shift))
+                    .unwrap_or(0)
+            };
+
+            high | low
+        }).collect();
+
+        let used_bits = (W * H) % bits;
+        if let (Some(last), true) = (words.last_mut(), used_bits > 0) {
+            *last &= (1 << used_bits) - 1;
+        }
+
+        BitBoard { words }
+    }
+
+    /// Moves every bit toward lower positions by the given number of bits, dropping the bits
+    /// moved before the first position.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - the number of bits to move by
+    fn shift_bits_down(&self, amount: usize) -> BitBoard<W, H> {
+        let bits = u64::BITS as usize;
+        let (word_shift, bit_shift) = (amount / bits, (amount % bits) as u32);
+        let word_at = |index: usize| self.words.get(index).copied().unwrap_or(0);
+
+        let words = (0..self.words.len()).map(|index| {
+            let low = word_at(index + word_shift) >> bit_shift;
+            let high = match bit_shift {
+                0 => 0,
+                _ => word_at(index + word_shift + 1) << (u64::BITS - bit_shift)
+            };
+
+            high | low
+        }).collect();
+
+        BitBoard { words }
+    }
+
+    /// Finds the word and the bit within that word that store a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to find
+    fn bit_pos(pos: Pos<W, H>) -> (usize, u32) {
+        let index = pos.y() * W + pos.x();
+        (index / u64::BITS as usize, (index % u64::BITS as usize) as u32)
+    }
+
+}
+
+impl<const W: usize, const H: usize> Default for BitBoard<W, H> {
+    fn default() -> Self {
+        BitBoard::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BitBoard, Direction, Pos};
+
+    #[test]
+    fn new_no_bits_set() {
+        let bits = BitBoard::<9, 8>::new();
+        assert!(bits.is_empty());
+        assert_eq!(0, bits.count());
+    }
+
+    #[test]
+    fn set_across_words_get_and_iter() {
+        let mut bits = BitBoard::<9, 8>::new();
+        bits.set(Pos::new(0, 0), true);
+        bits.set(Pos::new(8, 7), true);
+        bits.set(Pos::new(1, 7), true);
+        bits.set(Pos::new(1, 7), false);
+
+        assert!(bits.get(Pos::new(0, 0)));
+        assert!(bits.get(Pos::new(8, 7)));
+        assert!(!bits.get(Pos::new(1, 7)));
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(8, 7)], bits.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn and_keeps_shared_bits() {
+        let mut first = BitBoard::<9, 8>::new();
+        first.set(Pos::new(2, 3), true);
+        first.set(Pos::new(4, 5), true);
+
+        let mut second = BitBoard::<9, 8>::new();
+        second.set(Pos::new(4, 5), true);
+        second.set(Pos::new(6, 7), true);
+
+        assert_eq!(vec![Pos::new(4, 5)], first.and(&second).iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn shift_each_direction_drops_edges() {
+        let mut bits = BitBoard::<9, 8>::new();
+        bits.set(Pos::new(0, 0), true);
+        bits.set(Pos::new(8, 3), true);
+        bits.set(Pos::new(4, 7), true);
+
+        let to_vec = |bits: BitBoard<9, 8>| bits.iter().collect::<Vec<_>>();
+        assert_eq!(vec![Pos::new(0, 1), Pos::new(8, 4)], to_vec(bits.shift(Direction::North)));
+        assert_eq!(vec![Pos::new(8, 2), Pos::new(4, 6)], to_vec(bits.shift(Direction::South)));
+        assert_eq!(vec![Pos::new(1, 0), Pos::new(5, 7)], to_vec(bits.shift(Direction::East)));
+        assert_eq!(vec![Pos::new(7, 3), Pos::new(3, 7)], to_vec(bits.shift(Direction::West)));
+    }
+
+    #[test]
+    fn shift_across_many_words_matches_positions() {
+        let mut bits = BitBoard::<20, 20>::new();
+        let positions = [Pos::new(0, 0), Pos::new(19, 3), Pos::new(7, 12), Pos::new(13, 19)];
+        for pos in positions {
+            bits.set(pos, true);
+        }
+
+        for dir in [Direction::North, Direction::South, Direction::East, Direction::West] {
+            let mut expected: Vec<_> = positions.iter()
+                .filter_map(|pos| pos.adjacent(dir).ok())
+                .collect();
+            expected.sort_by_key(|pos| (pos.y(), pos.x()));
+
+            assert_eq!(expected, bits.shift(dir).iter().collect::<Vec<_>>());
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use std::array::from_fn;
 use std::cmp::Ordering::{Equal, Greater, Less};
-use std::collections::{BTreeSet, VecDeque};
-use crate::position::{Col, Pos};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::hash::Hash;
+use crate::bitboard::BitBoard;
+use crate::position::{Axis, Col, Direction, Pos};
 
 use std::ops::BitAnd;
 use crate::BoardError::NonAdjacent;
@@ -16,12 +18,70 @@ pub trait Piece: Copy + From<Self::MatchType> + Default + BitAnd<Output=Self> +
     /// A piece that matches no match types and is treated as empty.
     const AIR: Self;
 
+    /// Combines this piece with another piece that lands on top of it. Returns the upgraded
+    /// piece if the two pieces can merge or `None` if they cannot. By default, pieces never merge.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the piece that landed on top of this piece
+    fn merge(self, other: Self) -> Option<Self> {
+        let _ = other;
+        None
+    }
+
+    /// Decides which way this piece moves under gravity in
+    /// [`BoardState::apply_gravity_to_board`]. Pieces with a positive weight fall, pieces with a
+    /// negative weight rise, and pieces with a weight of zero stay in place and block other
+    /// pieces. The weight of air is ignored. By default, every piece falls.
+    fn weight(&self) -> i32 {
+        1
+    }
+
 }
 
 /// Defines errors possible from [`Board`] methods.
 #[derive(Debug, PartialEq, Eq)]
 pub enum BoardError<const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
-    NonAdjacent(Pos<BOARD_WIDTH, BOARD_HEIGHT>, Pos<BOARD_WIDTH, BOARD_HEIGHT>)
+    NonAdjacent(Pos<BOARD_WIDTH, BOARD_HEIGHT>, Pos<BOARD_WIDTH, BOARD_HEIGHT>),
+    OutsideBarrier(Pos<BOARD_WIDTH, BOARD_HEIGHT>, Direction)
+}
+
+/// The direction a falling piece shifts when it could move equally far left or right to reach
+/// an empty space during [`BoardState::apply_gravity_to_board_with_bias`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GravityBias {
+    #[default]
+    Left,
+    Right
+}
+
+/// One step of pieces falling under gravity. The piece at `from` moves to `to`, and whatever was
+/// at `to`, usually air, moves to `from`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GravityMove<const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+
+    /// The position of the moving piece before the move
+    pub from: Pos<BOARD_WIDTH, BOARD_HEIGHT>,
+
+    /// The position of the moving piece after the move
+    pub to: Pos<BOARD_WIDTH, BOARD_HEIGHT>
+
+}
+
+/// The changes made by [`BoardState::apply_merge_gravity`], in the order they were made.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MergeGravityResult<const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+
+    /// The swaps made to move the pieces, in the order they were applied
+    pub moves: Vec<(Pos<BOARD_WIDTH, BOARD_HEIGHT>, Pos<BOARD_WIDTH, BOARD_HEIGHT>)>,
+
+    /// Each merge as the merged piece's original position paired with the upgraded piece's
+    /// position. The merged piece is replaced with air.
+    pub merges: Vec<(Pos<BOARD_WIDTH, BOARD_HEIGHT>, Pos<BOARD_WIDTH, BOARD_HEIGHT>)>
+
 }
 
 /// Contains zero or many pieces and represents the current state
@@ -34,17 +94,26 @@ pub enum BoardError<const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
 ///
 /// The board's lack of default restrictions allows games to implement
 /// their own unique or non-standard rules.
+///
+/// With the `serde` feature enabled, the board can be serialized whenever its pieces
+/// can be serialized. Deserializing fails if the data has a barrier on the edge of the
+/// board (see [`BoardState::barriers_valid`]).
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "P: serde::Serialize")))]
 pub struct BoardState<
     P,
     const WIDTH: usize,
     const HEIGHT: usize
 > {
+    #[cfg_attr(feature = "serde", serde(with = "grid_serde"))]
     pieces: [[P; HEIGHT]; WIDTH],
 
     // TODO: The inner array is one element too large, but generic const exprs aren't stable yet
+    #[cfg_attr(feature = "serde", serde(with = "grid_serde"))]
     horizontal_barriers: [[bool; HEIGHT]; WIDTH],
+
+    #[cfg_attr(feature = "serde", serde(with = "grid_serde"))]
     vertical_barriers: [[bool; WIDTH]; HEIGHT]
 }
 
@@ -59,6 +128,92 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
         }
     }
 
+    /// Creates a board with the given pieces and no barriers.
+    ///
+    /// # Arguments
+    ///
+    /// * `pieces` - the pieces in each column, from the bottom of the column up
+    pub fn from_array(pieces: [[P; H]; W]) -> BoardState<P, W, H> {
+        BoardState {
+            pieces,
+            horizontal_barriers: [[false; H]; W],
+            vertical_barriers: [[false; W]; H]
+        }
+    }
+
+    /// Creates a board with the given rows of pieces and no barriers. Rows are given from the
+    /// bottom of the board up, and any rows that are not given are filled with default pieces.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - the pieces in each row, from left to right
+    ///
+    /// # Panics
+    ///
+    /// Panics if more rows are given than the height of the board.
+    pub fn from_rows(rows: impl IntoIterator<Item=[P; W]>) -> BoardState<P, W, H> {
+        let mut board = BoardState::new();
+
+        for (y, row) in rows.into_iter().enumerate() {
+            if y >= H {
+                panic!("Tried to create board with more than {H} rows");
+            }
+
+            for (x, piece) in row.into_iter().enumerate() {
+                board.pieces[x][y] = piece;
+            }
+        }
+
+        board
+    }
+
+    /// Creates a board from its pieces and barriers, checking that the barriers are valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `pieces` - the pieces in each column, from the bottom of the column up
+    /// * `horizontal_barriers` - for each column, whether there is a barrier between each
+    ///                           position and the position above it. The last entry of each
+    ///                           column is above the top of the board and must be false.
+    /// * `vertical_barriers` - for each row, whether there is a barrier between each position
+    ///                         and the position to its right. The last entry of each row is
+    ///                         past the right edge of the board and must be false.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BoardError::OutsideBarrier`] with the position and direction of the first
+    /// barrier found on the edge of the board.
+    #[allow(clippy::doc_overindented_list_items)]
+    pub fn try_from_parts(pieces: [[P; H]; W], horizontal_barriers: [[bool; H]; W],
+                          vertical_barriers: [[bool; W]; H]) -> Result<BoardState<P, W, H>, BoardError<W, H>> {
+        let board = BoardState { pieces, horizontal_barriers, vertical_barriers };
+
+        match board.first_outside_barrier() {
+            Some((pos, dir)) => Err(BoardError::OutsideBarrier(pos, dir)),
+            None => Ok(board)
+        }
+    }
+
+    /// Checks that there are no barriers on the edge of the board. The barrier arrays have one
+    /// unused entry per column above the top of the board and one per row past the right edge,
+    /// which should always be false. Only a board built from corrupt data can fail this check.
+    pub fn barriers_valid(&self) -> bool {
+        self.first_outside_barrier().is_none()
+    }
+
+    /// Finds the first barrier on the edge of the board, checking the top edge before the
+    /// right edge. Returns the position next to the barrier and the direction of the barrier
+    /// from that position, or `None` if there are no barriers on the edge.
+    fn first_outside_barrier(&self) -> Option<(Pos<W, H>, Direction)> {
+        let top_barrier = self.horizontal_barriers.iter()
+            .position(|col_barriers| col_barriers.last() == Some(&true))
+            .map(|x| (Pos::new(x, H - 1), Direction::North));
+
+        top_barrier.or_else(|| self.vertical_barriers.iter()
+            .position(|row_barriers| row_barriers.last() == Some(&true))
+            .map(|y| (Pos::new(W - 1, y), Direction::East)))
+    }
+
     /// Gets the type of a piece at a certain position.
     ///
     /// # Arguments
@@ -68,6 +223,24 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
         self.pieces[pos.x()][pos.y()]
     }
 
+    /// Borrows every piece on the board at once, which avoids a method call per position when
+    /// reading the whole board. The grid is column-major: `grid()[x][y]` is the piece at
+    /// `(x, y)`, and `y` increases upward from the bottom row.
+    pub fn grid(&self) -> &[[P; H]; W] {
+        &self.pieces
+    }
+
+    /// Gets the type of a piece at the given coordinates, or `None` if the coordinates are
+    /// outside the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - the horizontal component of the piece's position
+    /// * `y` - the vertical component of the piece's position
+    pub fn try_piece(&self, x: usize, y: usize) -> Option<P> {
+        Pos::<W, H>::try_new(x, y).ok().map(|pos| self.piece(pos))
+    }
+
     /// Replaces a piece at the given position and returns the previous piece.
     ///
     /// # Arguments
@@ -80,6 +253,19 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
         old_piece
     }
 
+    /// Replaces every piece on the board, including air, with the result of a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - receives each position and its current piece and returns the new piece
+    pub fn map_in_place<F: FnMut(Pos<W, H>, P) -> P>(&mut self, mut f: F) {
+        for (x, col) in self.pieces.iter_mut().enumerate() {
+            for (y, piece) in col.iter_mut().enumerate() {
+                *piece = f(Pos::new(x, y), *piece);
+            }
+        }
+    }
+
     /// Swap two pieces on the board. The order of two positions provided does not matter.
     ///
     /// # Arguments
@@ -91,6 +277,18 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
         self.set_piece(second, old_first);
     }
 
+    /// Swap two pieces on the board and returns the pieces in their new positions, as
+    /// (piece now at `first`, piece now at `second`).
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    pub fn swap_reporting(&mut self, first: Pos<W, H>, second: Pos<W, H>) -> (P, P) {
+        self.swap(first, second);
+        (self.piece(first), self.piece(second))
+    }
+
     /// Finds the y position of a space with air that represents the "surface" of the given column.
     /// The surface is the position where a piece would be if it was dropped into the column from
     /// the top of the board.
@@ -127,6 +325,107 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
         }
     }
 
+    /// Finds the empty positions where new pieces would enter the board from the top. In each
+    /// column, these are all the positions from the column's [`BoardState::surface`] up to the
+    /// top of the board. Since the surface is above every piece and barrier in the column, none
+    /// of these positions are blocked. Positions are grouped by column, from left to right, and
+    /// listed from the bottom up within each column.
+    pub fn open_surface_cells(&self) -> Vec<Pos<W, H>> {
+        (0..W).flat_map(|x| {
+            let surface = self.surface(Col::new(x)).unwrap_or(H);
+            (surface..H).map(move |y| Pos::new(x, y))
+        }).collect()
+    }
+
+    /// Gets the height of a column: the y-coordinate just above its topmost piece. Unlike
+    /// [`BoardState::surface`], barriers are ignored, and a full column has a height equal to
+    /// the height of the board. A column with no pieces has a height of zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - column to find the height of
+    pub fn column_height(&self, col: Col<W>) -> usize {
+        self.pieces[col.x].iter()
+            .rposition(|&piece| piece != P::AIR)
+            .map(|top_y| top_y + 1)
+            .unwrap_or(0)
+    }
+
+    /// Finds the topmost piece that is not air in a column, along with its position. When the
+    /// column has no barriers above its pieces, this is the piece just below the column's
+    /// [`BoardState::surface`]. Returns `None` if the column only contains air.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - column to find the top piece of
+    pub fn top_piece(&self, col: Col<W>) -> Option<(Pos<W, H>, P)> {
+        self.pieces[col.x].iter()
+            .rposition(|&piece| piece != P::AIR)
+            .map(|top_y| (Pos::new(col.x, top_y), self.pieces[col.x][top_y]))
+    }
+
+    /// Checks whether the height of a column, as found by [`BoardState::column_height`], is
+    /// greater than the given limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - column to check
+    /// * `limit` - the greatest height allowed
+    pub fn is_over_threshold(&self, col: Col<W>, limit: usize) -> bool {
+        self.column_height(col) > limit
+    }
+
+    /// Finds where a piece dropped into the top of a column would end up after
+    /// [`BoardState::apply_gravity_to_board`], including any sideways sliding. Other floating
+    /// pieces move at the same time as the dropped piece, and the dropped piece moves according
+    /// to its [`Piece::weight`]. Returns `None` if the top of the column is already filled. The
+    /// board is not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - column to drop a piece into
+    /// * `piece` - the piece to drop
+    pub fn landing_position(&self, col: Col<W>, piece: P) -> Option<Pos<W, H>> {
+        if H == 0 || self.pieces[col.x][H - 1] != P::AIR {
+            return None;
+        }
+
+        let mut preview_board = *self;
+
+        let mut landing_pos = Pos::new(col.x, H - 1);
+        preview_board.set_piece(landing_pos, piece);
+
+        for (first, second) in preview_board.apply_gravity_to_board() {
+            if landing_pos == first {
+                landing_pos = second;
+            } else if landing_pos == second {
+                landing_pos = first;
+            }
+        }
+
+        Some(landing_pos)
+    }
+
+    /// Finds all pieces that would fall if gravity were applied: pieces that are not air, with
+    /// air directly below them and no barrier in between. Pieces on the bottom row never float.
+    pub fn floating_pieces(&self) -> Vec<Pos<W, H>> {
+        let mut floating = Vec::new();
+
+        for x in 0..W {
+            for y in 1..H {
+                let pos = Pos::new(x, y);
+                let below_pos = Pos::new(x, y - 1);
+
+                if self.piece(pos) != P::AIR && self.piece(below_pos) == P::AIR
+                    && !self.has_barrier_between(pos, below_pos) {
+                    floating.push(pos);
+                }
+            }
+        }
+
+        floating
+    }
+
     /// Moves all pieces in the given column as if they were falling due to gravity. The bottom of
     /// the board, horizontal barriers, and other pieces will block the fall of pieces in the given
     /// column. This method returns (before, after) pairs of y-coordinates that describe how the
@@ -136,18 +435,52 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
     ///
     /// `col` - column to apply gravity to
     pub fn apply_gravity_to_column(&mut self, col: Col<W>) -> Vec<(usize, usize)> {
-        let x = col.x;
+        self.apply_gravity_to_column_with(col, true)
+    }
 
-        let mut air_ys = VecDeque::new();
-        let mut moves = Vec::new();
+    /// Moves all pieces in the given column as if they were falling due to gravity, exactly like
+    /// [`BoardState::apply_gravity_to_column`], but optionally leaves blocked segments alone.
+    /// A blocked segment is part of the column that has a horizontal barrier directly above it,
+    /// so no pieces can enter it from above. Games can use this for barriers that represent
+    /// immovable obstacles, where the pieces trapped underneath are stuck in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - column to apply gravity to
+    /// * `compact_blocked` - whether pieces inside blocked segments fall. If false, only the
+    ///                       pieces above the highest barrier in the column move.
+    #[allow(clippy::doc_overindented_list_items)]
+    pub fn apply_gravity_to_column_with(&mut self, col: Col<W>, compact_blocked: bool) -> Vec<(usize, usize)> {
+        let x = col.x;
 
         if H == 0 {
-            return moves;
+            return Vec::new();
         }
 
-        let horizontal_offset = Pos::new(0, 1);
+        let first_unblocked_y = match compact_blocked {
+            true => 0,
+            false => (1..H).rev()
+                .find(|&y| self.has_barrier_between(Pos::new(x, y - 1), Pos::new(x, y)))
+                .unwrap_or(0)
+        };
+
+        self.apply_gravity_to_column_range(x, first_unblocked_y, H - 1)
+    }
+
+    /// Moves the pieces between two y-coordinates in a column as if they were falling due to
+    /// gravity, treating `bottom_y` as the floor. Barriers below `top_y` block the fall. Returns
+    /// the (before, after) y-coordinates of the pieces that moved, in the order they were moved.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - the x-coordinate of the column
+    /// * `bottom_y` - the lowest y-coordinate in the range, included in the range
+    /// * `top_y` - the highest y-coordinate in the range, included in the range
+    fn apply_gravity_to_column_range(&mut self, x: usize, bottom_y: usize, top_y: usize) -> Vec<(usize, usize)> {
+        let mut air_ys = VecDeque::new();
+        let mut moves = Vec::new();
 
-        for y in 0..H {
+        for y in bottom_y..=top_y {
             let pos = Pos::new(x, y);
 
             if self.pieces[x][y] == P::AIR {
@@ -158,9 +491,7 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
                 air_ys.push_back(y);
             }
 
-            let has_barrier_above = (pos + horizontal_offset)
-                .map(|p| self.has_barrier_between(pos, p))
-                .unwrap_or(false);
+            let has_barrier_above = y < top_y && self.has_barrier_between(pos, Pos::new(x, y + 1));
             if has_barrier_above {
                 air_ys.clear();
             }
@@ -169,15 +500,124 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
         moves
     }
 
+    /// Moves all pieces in the given columns as if they were falling due to gravity, leaving
+    /// all other columns untouched. Each column is handled the same way as in
+    /// [`BoardState::apply_gravity_to_column`]. Returns the x-coordinate of each column paired
+    /// with the (before, after) y-coordinates of the pieces moved in that column, in the order
+    /// the columns were provided.
+    ///
+    /// # Arguments
+    ///
+    /// `cols` - columns to apply gravity to
+    pub fn apply_gravity_to_columns(&mut self, cols: &[Col<W>]) -> Vec<(usize, Vec<(usize, usize)>)> {
+        cols.iter()
+            .map(|&col| (col.x, self.apply_gravity_to_column(col)))
+            .collect()
+    }
+
+    /// Moves the pieces inside a rectangle as if they were falling due to gravity, leaving the
+    /// rest of the board untouched. The bottom edge of the rectangle acts as the floor, and
+    /// pieces never move between columns. Horizontal barriers and other pieces inside the
+    /// rectangle block the fall the same way as in [`BoardState::apply_gravity_to_column`].
+    /// Returns the swaps that were made, in the order in which they were applied. The corners
+    /// may be given in either order.
+    ///
+    /// # Arguments
+    ///
+    /// * `top_left` - the top-left corner of the rectangle, included in the rectangle
+    /// * `bottom_right` - the bottom-right corner of the rectangle, included in the rectangle
+    pub fn apply_gravity_to_region(&mut self, top_left: Pos<W, H>,
+                                   bottom_right: Pos<W, H>) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let min_x = top_left.x().min(bottom_right.x());
+        let max_x = top_left.x().max(bottom_right.x());
+        let min_y = top_left.y().min(bottom_right.y());
+        let max_y = top_left.y().max(bottom_right.y());
+
+        (min_x..=max_x)
+            .flat_map(|x| self.apply_gravity_to_column_range(x, min_y, max_y)
+                .into_iter()
+                .map(move |(from_y, to_y)| (Pos::new(x, from_y), Pos::new(x, to_y))))
+            .collect()
+    }
+
     /// Makes all the pieces on the board fall as if there was gravity. Returns a vector of swaps
     /// that were made to move the pieces, which is useful for producing an animation of the pieces
     /// falling. For example, if the resultant vector contains ((2, 3), (2, 4)), then (2, 3) and
     /// (2, 4) were swapped. The swaps are in the order in which they were applied to the board.
+    ///
+    /// When a piece could shift equally far left or right into an empty space, it shifts left.
+    /// Use [`BoardState::apply_gravity_to_board_with_bias`] to choose the direction.
+    ///
+    /// Pieces that do not fall according to [`Piece::weight`] never shift sideways. Pieces that
+    /// stay in place block other pieces like barriers. Rising pieces move first: each one passes
+    /// through the falling pieces above it in its column, keeping their order, and then floats
+    /// up until it reaches a barrier, a piece that does not fall, or the top of the board.
+    /// Falling pieces then fall as usual, stopping on top of rising pieces.
     pub fn apply_gravity_to_board(&mut self) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        self.apply_gravity_to_board_with_bias(GravityBias::Left)
+    }
+
+    /// Computes the swaps that [`BoardState::apply_gravity_to_board`] would make without
+    /// changing this board. Applying the swaps in order to a copy of this board produces the
+    /// same result as applying gravity to it.
+    pub fn preview_gravity(&self) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        self.clone().apply_gravity_to_board()
+    }
+
+    /// Applies [`BoardState::apply_gravity_to_board`] repeatedly until no pieces are floating,
+    /// as reported by [`BoardState::floating_pieces`]. Returns the swaps from every pass, in the
+    /// order in which they were applied. Stops early if a pass does not move any pieces.
+    pub fn settle(&mut self) -> Vec<(Pos<W, H>, Pos<W, H>)> {
         let mut moves = Vec::new();
 
-        let mut air_by_row = self.scan_row_air();
-        let mut air_by_col = self.scan_col_air();
+        while !self.floating_pieces().is_empty() {
+            let mut pass_moves = self.apply_gravity_to_board();
+
+            if pass_moves.is_empty() {
+                break;
+            }
+
+            moves.append(&mut pass_moves);
+        }
+
+        moves
+    }
+
+    /// Makes all the pieces on the board fall exactly like
+    /// [`BoardState::apply_gravity_to_board`], but describes each swap as a [`GravityMove`].
+    /// Each move corresponds to the swap at the same index in the other method's result.
+    pub fn apply_gravity_detailed(&mut self) -> Vec<GravityMove<W, H>> {
+        self.apply_gravity_to_board().into_iter()
+            .map(|(from, to)| GravityMove { from, to })
+            .collect()
+    }
+
+    /// Makes all the pieces on the board fall as if there was gravity, exactly like
+    /// [`BoardState::apply_gravity_to_board`], except that ties between empty spaces equally far
+    /// to the left and right of a falling piece are broken in the given direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `bias` - the direction to shift pieces when both directions are equally close
+    pub fn apply_gravity_to_board_with_bias(&mut self, bias: GravityBias) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        self.apply_gravity_skipping(bias, &BitBoard::new())
+    }
+
+    /// Makes all the pieces on the board move under gravity like
+    /// [`BoardState::apply_gravity_to_board_with_bias`], except that the positions set in
+    /// `frozen` keep whatever they contain, even air, and block other pieces as if they held a
+    /// piece that stays in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `bias` - the direction to shift pieces when both directions are equally close
+    /// * `frozen` - the positions that gravity skips
+    pub(crate) fn apply_gravity_skipping(&mut self, bias: GravityBias,
+                                         frozen: &BitBoard<W, H>) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let mut moves = self.apply_rising_gravity(frozen);
+
+        let mut air_by_row = self.scan_row_air(frozen);
+        let mut air_by_col = self.scan_col_air(frozen);
 
         // Initially, fill the queue with every position on the board
         let mut pos_to_update: VecDeque<Pos<W, H>> = (0..H)
@@ -188,7 +628,7 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
             let x = pos.x();
             let y = pos.y();
 
-            if self.pieces[x][y] != P::AIR {
+            if self.pieces[x][y] != P::AIR && !self.holds_in_place(pos, frozen) {
                 let col_air_interval = BoardState::<P, W, H>::col_air_interval(&mut air_by_col, x, y)
                     .unwrap();
 
@@ -225,7 +665,7 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
                 // of the board
                 if new_y > col_air_interval.begin_y {
                     let y_below = new_y - 1;
-                    if let Some(air_x) = self.closest_air_in_row(x, y_below, &mut air_by_row) {
+                    if let Some(air_x) = self.closest_air_in_row(x, y_below, &mut air_by_row, bias) {
 
                         // Shift pieces in the row below so that air is directly below the piece
                         // that just fell
@@ -265,542 +705,2386 @@ impl<P: Piece, const W: usize, const H: usize> BoardState<P, W, H> {
         moves
     }
 
-    /// Checks whether there is a barrier between two positions.
+    /// Cyclically shifts all pieces in the given column. Pieces shifted past the top of the board
+    /// wrap around to the bottom, and vice versa. Barriers do not block the shift. Returns the
+    /// swaps that were made to rotate the column, in the order in which they were applied.
     ///
     /// # Arguments
     ///
-    /// * `first` - first position to check whether there is a barrier between
-    /// * `second` - second position to check whether there is a barrier between
-    pub fn has_barrier_between(&self, first: Pos<W, H>, second: Pos<W, H>) -> bool {
-        if BoardState::<P, W, H>::vertically_adjacent(first, second) {
-            let x = first.x();
-            let barrier_index = BoardState::<P, W, H>::horizontal_barrier_index(first, second);
-            return self.horizontal_barriers[x][barrier_index]
-        } else if BoardState::<P, W, H>::horizontally_adjacent(first, second) {
-            let y = first.y();
-            let barrier_index = BoardState::<P, W, H>::vertical_barrier_index(first, second);
-            return self.vertical_barriers[y][barrier_index]
+    /// * `col` - column to rotate
+    /// * `shift` - number of spaces to move each piece. Positive values move pieces up, and
+    ///             negative values move pieces down.
+    #[allow(clippy::doc_overindented_list_items)]
+    pub fn cycle_column(&mut self, col: Col<W>, shift: isize) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let mut moves = Vec::new();
+
+        if H == 0 {
+            return moves;
         }
 
-        false
+        let shift_up = shift.rem_euclid(H as isize) as usize;
+        if shift_up == 0 {
+            return moves;
+        }
+
+        // Rotating by reversing the whole column and then each of the two sections
+        // requires fewer swaps than shifting one space at a time
+        self.reverse_column_section(col.x, 0, H, &mut moves);
+        self.reverse_column_section(col.x, 0, shift_up, &mut moves);
+        self.reverse_column_section(col.x, shift_up, H, &mut moves);
+
+        moves
     }
 
-    /// Sets whether there is a barrier between two positions. The positions must be directly
-    /// adjacent in the vertical or the horizontal direction. There cannot be barriers between
-    /// diagonally adjacent positions.
+    /// Slides the pieces in a row by one space so that the piece at `from_x` moves into the space
+    /// at `to_x`. The pieces between the two columns, including the piece at `to_x`, each move one
+    /// space toward `from_x`. Barriers do not block the slide. Returns the swaps made to slide the
+    /// row, in the order in which they were applied.
     ///
     /// # Arguments
     ///
-    /// * `first` - first position to set a barrier between
-    /// * `second` - second position to set a barrier between
-    /// * `has_barrier` - whether there should be a barrier between the two positions
-    pub fn set_barrier_between(&mut self, first: Pos<W, H>, second: Pos<W, H>, has_barrier: bool) -> Result<(), BoardError<W, H>> {
-        if BoardState::<P, W, H>::vertically_adjacent(first, second) {
-            let x = first.x();
-            let barrier_index = BoardState::<P, W, H>::horizontal_barrier_index(first, second);
-            self.horizontal_barriers[x][barrier_index] = has_barrier;
-            return Ok(());
-        } else if BoardState::<P, W, H>::horizontally_adjacent(first, second) {
-            let y = first.y();
-            let barrier_index = BoardState::<P, W, H>::vertical_barrier_index(first, second);
-            self.vertical_barriers[y][barrier_index] = has_barrier;
-            return Ok(());
+    /// * `y` - index of the row to slide
+    /// * `from_x` - x-coordinate of the piece that will move to `to_x`
+    /// * `to_x` - destination of the piece at `from_x` after the slide
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row or either column is outside the board.
+    pub fn slide_row(&mut self, y: usize, from_x: usize, to_x: usize) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        if y >= H || from_x >= W || to_x >= W {
+            panic!("Tried to slide row outside board: row {y} from {from_x} to {to_x}");
         }
-        
-        Err(NonAdjacent(first, second))
+
+        self.rotate_row(y, from_x, to_x)
     }
 
-    /// Checks if two positions are horizontally adjacent.
+    /// Moves every piece that has air directly below it, with no barrier in between, down by
+    /// exactly one space. Pieces are moved starting from the bottom of the board, so a stack of
+    /// pieces falls together. Repeatedly calling this method until it returns no swaps produces
+    /// a gradual fall that ends in the same state as applying
+    /// [`BoardState::apply_gravity_to_column`] to every column. Unlike
+    /// [`BoardState::apply_gravity_to_board`], pieces are never shifted horizontally. Returns the
+    /// swaps that were made, in the order in which they were applied.
+    pub fn apply_gravity_step(&mut self) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let mut moves = Vec::new();
+
+        for y in 1..H {
+            for x in 0..W {
+                let pos = Pos::new(x, y);
+                let below_pos = Pos::new(x, y - 1);
+
+                if self.piece(pos) != P::AIR && self.piece(below_pos) == P::AIR
+                    && !self.has_barrier_between(pos, below_pos) {
+                    self.swap(pos, below_pos);
+                    moves.push((pos, below_pos));
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// Makes pieces fall like [`BoardState::apply_gravity_step`], but lets each piece fall up to
+    /// `max_fall` spaces instead of one. Pieces that would fall farther stop early, so calling
+    /// this method repeatedly until it returns no swaps settles the board. Returns the swaps
+    /// that were made, in the order in which they were applied.
     ///
     /// # Arguments
     ///
-    /// * `first` - first position to compare
-    /// * `second` - second position to compare
-    fn horizontally_adjacent(first: Pos<W, H>, second: Pos<W, H>) -> bool {
-        usize::abs_diff(first.x(), second.x()) == 1
-            && usize::abs_diff(first.y(), second.y()) == 0
+    /// * `max_fall` - the most spaces any piece can fall during this call
+    pub fn apply_gravity_to_board_limited(&mut self, max_fall: usize) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let mut moves = Vec::new();
+
+        for _ in 0..max_fall {
+            let step_moves = self.apply_gravity_step();
+
+            if step_moves.is_empty() {
+                break;
+            }
+
+            moves.extend(step_moves);
+        }
+
+        moves
     }
 
-    /// Checks if two positions are vertically adjacent.
+    /// Fills empty spaces with new pieces that enter the board from the given edge. In every
+    /// column (for the north and south edges) or row (for the east and west edges), spaces are
+    /// filled starting at the edge and moving inward until a piece, a barrier, or the opposite
+    /// edge is reached. Returns the filled positions in the order they were filled.
     ///
     /// # Arguments
     ///
-    /// * `first` - first position to compare
-    /// * `second` - second position to compare
-    fn vertically_adjacent(first: Pos<W, H>, second: Pos<W, H>) -> bool {
-        usize::abs_diff(first.x(), second.x()) == 0
-            && usize::abs_diff(first.y(), second.y()) == 1
+    /// * `edge` - the edge of the board that new pieces enter from
+    /// * `spawn` - creates the new piece for a position
+    pub fn refill_from<F: FnMut(Pos<W, H>) -> P>(&mut self, edge: Direction, spawn: F) -> Vec<Pos<W, H>> {
+        self.refill_skipping(edge, spawn, &BitBoard::new())
     }
 
-    /// Retrieves the index of a horizontal barrier within an array of horizontal barriers for
-    /// the same column.
+    /// Fills empty spaces like [`BoardState::refill_from`], except that the positions set in
+    /// `frozen` are never filled and stop the refill like a piece would.
     ///
     /// # Arguments
     ///
-    /// * `first` - first position that the barrier exists between
-    /// * `second` - second position that the barrier exists between
-    fn horizontal_barrier_index(first: Pos<W, H>, second: Pos<W, H>) -> usize {
-        first.y().min(second.y())
+    /// * `edge` - the edge of the board that new pieces enter from
+    /// * `spawn` - creates the new piece for a position
+    /// * `frozen` - the positions that are never filled
+    pub(crate) fn refill_skipping<F: FnMut(Pos<W, H>) -> P>(&mut self, edge: Direction, mut spawn: F,
+                                                             frozen: &BitBoard<W, H>) -> Vec<Pos<W, H>> {
+        let mut filled = Vec::new();
+
+        if W == 0 || H == 0 {
+            return filled;
+        }
+
+        let (starts, inward): (Vec<Pos<W, H>>, Direction) = match edge {
+            Direction::North => ((0..W).map(|x| Pos::new(x, H - 1)).collect(), Direction::South),
+            Direction::South => ((0..W).map(|x| Pos::new(x, 0)).collect(), Direction::North),
+            Direction::East => ((0..H).map(|y| Pos::new(W - 1, y)).collect(), Direction::West),
+            Direction::West => ((0..H).map(|y| Pos::new(0, y)).collect(), Direction::East)
+        };
+
+        for start in starts {
+            let mut next_pos = Some(start);
+
+            while let Some(pos) = next_pos {
+                if self.piece(pos) != P::AIR || frozen.get(pos) {
+                    break;
+                }
+
+                self.set_piece(pos, spawn(pos));
+                filled.push(pos);
+
+                next_pos = pos.adjacent(inward).ok()
+                    .filter(|&inner_pos| !self.has_barrier_between(pos, inner_pos));
+            }
+        }
+
+        filled
     }
 
-    /// Retrieves the index of a vertical barrier within an array of vertical barriers for
-    /// the same row.
+    /// Fills the empty spaces open to the top of the board, like
+    /// [`BoardState::refill_from`] with the north edge, using pieces chosen at random. Each
+    /// piece is chosen with a probability proportional to its weight. Returns the filled
+    /// positions, in the order they were filled, with the pieces placed there.
     ///
     /// # Arguments
     ///
-    /// * `first` - first position that the barrier exists between
-    /// * `second` - second position that the barrier exists between
-    fn vertical_barrier_index(first: Pos<W, H>, second: Pos<W, H>) -> usize {
-        first.x().min(second.x())
-    }
+    /// * `rng` - source of randomness used to choose pieces
+    /// * `weights` - pieces that can be chosen and their relative weights
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are empty spaces to fill but the weights add up to zero.
+    #[cfg(feature = "rand")]
+    pub fn refill_weighted<R: rand::Rng>(&mut self, rng: &mut R, weights: &[(P, u32)]) -> Vec<(Pos<W, H>, P)> {
+        let total_weight: u64 = weights.iter().map(|&(_, weight)| weight as u64).sum();
+
+        let filled = self.refill_from(Direction::North, |_| {
+            assert!(total_weight > 0, "cannot refill with weights that add up to zero");
+
+            let mut choice = rng.gen_range(0..total_weight);
+            for &(piece, weight) in weights {
+                if choice < weight as u64 {
+                    return piece;
+                }
 
-    /// Scans the whole board to find air intervals for each column.
-    fn scan_row_air(&self) -> [Vec<RowAirInterval>; H] {
-        let mut intervals: [Vec<RowAirInterval>; H] = from_fn(|_| Vec::new());
+                choice -= weight as u64;
+            }
 
-        for (y, row_intervals) in intervals.iter_mut().enumerate() {
-            let mut begin_x = 0;
-            let mut air_count = 0;
+            unreachable!("random choice is always less than the total weight")
+        });
+
+        filled.into_iter().map(|pos| (pos, self.piece(pos))).collect()
+    }
+
+    /// Makes all the pieces on the board fall straight down, then lets pieces that are blocked
+    /// from falling slide one space diagonally down into an empty space, repeating until no
+    /// piece can move. A piece is blocked when there is a piece or barrier directly below it. It
+    /// can only slide if the space beside it and the space below that are empty and there are no
+    /// barriers in the way. Sliding left is preferred over sliding right.
+    ///
+    /// Returns the swaps that were made to move the pieces, in the order in which they were
+    /// applied. Unlike [`BoardState::apply_gravity_to_board`], a swap may be between diagonal
+    /// positions.
+    pub fn apply_diagonal_gravity(&mut self) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let mut moves = Vec::new();
 
+        loop {
             for x in 0..W {
-                if self.pieces[x][y] == P::AIR {
-                    air_count += 1;
-                }
+                moves.extend(
+                    self.apply_gravity_to_column(Col::new(x)).into_iter()
+                        .map(|(from_y, to_y)| (Pos::new(x, from_y), Pos::new(x, to_y)))
+                );
+            }
 
-                let pos = Pos::new(x, y);
-                let right_pos = pos + Pos::new(1, 0);
-                let barrier_right = right_pos.map(|p| self.has_barrier_between(pos, p))
-                    .unwrap_or(false);
+            let mut has_slid = false;
 
-                // End an interval at a barrier or at the top of the board
-                if barrier_right || x == W - 1 {
-                    let interval = RowAirInterval {
-                        begin_x,
-                        end_x: x,
-                        air_count,
-                    };
+            for y in 1..H {
+                for x in 0..W {
+                    let pos = Pos::new(x, y);
+                    let below_pos = Pos::new(x, y - 1);
 
-                    row_intervals.push(interval);
+                    let is_blocked = self.piece(below_pos) != P::AIR || self.has_barrier_between(pos, below_pos);
+                    if self.piece(pos) == P::AIR || !is_blocked {
+                        continue;
+                    }
 
-                    begin_x = x + 1;
-                    air_count = 0;
+                    let slide_pos = [Direction::West, Direction::East].into_iter()
+                        .filter_map(|dir| pos.adjacent(dir).ok())
+                        .filter(|&side_pos| self.piece(side_pos) == P::AIR && !self.has_barrier_between(pos, side_pos))
+                        .map(|side_pos| (side_pos, Pos::new(side_pos.x(), y - 1)))
+                        .find(|&(side_pos, diagonal_pos)| self.piece(diagonal_pos) == P::AIR
+                            && !self.has_barrier_between(side_pos, diagonal_pos))
+                        .map(|(_, diagonal_pos)| diagonal_pos);
+
+                    if let Some(diagonal_pos) = slide_pos {
+                        self.swap(pos, diagonal_pos);
+                        moves.push((pos, diagonal_pos));
+                        has_slid = true;
+                    }
                 }
+            }
 
+            if !has_slid {
+                break;
             }
         }
 
-        intervals
+        moves
     }
 
-    /// Scans the whole board to find air intervals for each column..
-    fn scan_col_air(&self) -> [Vec<ColAirInterval>; W] {
-        let mut intervals: [Vec<ColAirInterval>; W] = from_fn(|_| Vec::new());
+    /// Makes all the pieces on the board fall straight down, merging a falling piece into the
+    /// piece it lands on whenever [`Piece::merge`] allows it. Only pieces that fall during this
+    /// pass merge, so pieces already resting on each other are left alone. A piece created by a
+    /// merge does not merge again during the same fall. Horizontal barriers block the fall of
+    /// pieces and prevent merges across them. Returns the swaps and merges that were made.
+    pub fn apply_merge_gravity(&mut self) -> MergeGravityResult<W, H> {
+        let mut moves = Vec::new();
+        let mut merges = Vec::new();
 
-        for (x, col_intervals) in intervals.iter_mut().enumerate() {
-            let mut begin_y = 0;
-            let mut air_ys = BTreeSet::new();
+        for x in 0..W {
+            let mut next_y = 0;
+            let mut last_landed: Option<(usize, bool)> = None;
 
             for y in 0..H {
-                if self.pieces[x][y] == P::AIR {
-                    air_ys.insert(y);
+                let piece = self.pieces[x][y];
+
+                if piece != P::AIR {
+                    let has_fallen = y != next_y;
+                    let merged_piece = match last_landed {
+                        Some((landed_y, false)) if has_fallen => self.pieces[x][landed_y].merge(piece)
+                            .map(|merged_piece| (landed_y, merged_piece)),
+                        _ => None
+                    };
+
+                    if let Some((landed_y, merged_piece)) = merged_piece {
+                        self.pieces[x][landed_y] = merged_piece;
+                        self.pieces[x][y] = P::AIR;
+                        merges.push((Pos::new(x, y), Pos::new(x, landed_y)));
+                        last_landed = Some((landed_y, true));
+                    } else {
+                        if has_fallen {
+                            self.swap(Pos::new(x, y), Pos::new(x, next_y));
+                            moves.push((Pos::new(x, y), Pos::new(x, next_y)));
+                        }
+
+                        last_landed = Some((next_y, false));
+                        next_y += 1;
+                    }
                 }
 
-                let pos = Pos::new(x, y);
-                let pos_above = pos + Pos::new(0, 1);
-                let barrier_above = pos_above.map(|p| self.has_barrier_between(pos, p))
-                    .unwrap_or(false);
+                // Pieces above a barrier cannot fall into or merge with pieces below it
+                if y + 1 < H && self.horizontal_barriers[x][y] {
+                    next_y = y + 1;
+                    last_landed = None;
+                }
+            }
+        }
 
-                // End an interval at a barrier or at the top of the board
-                if barrier_above || y == H - 1 {
-                    let interval = ColAirInterval {
-                        begin_y,
-                        end_y: y,
-                        air_ys: air_ys.clone(),
-                    };
+        MergeGravityResult { moves, merges }
+    }
 
-                    col_intervals.push(interval);
+    /// Gets the positions of the four corners of the board along with the pieces there, in the
+    /// order bottom left, bottom right, top left, and top right. On a board that is only one
+    /// column wide or one row tall, some corners are the same position. Returns `None` if the
+    /// board has no positions.
+    pub fn corners(&self) -> Option<[(Pos<W, H>, P); 4]> {
+        if W == 0 || H == 0 {
+            return None;
+        }
 
-                    begin_y = y + 1;
-                    air_ys.clear();
-                }
+        Some([(0, 0), (W - 1, 0), (0, H - 1), (W - 1, H - 1)].map(|(x, y)| {
+            let pos = Pos::new(x, y);
+            (pos, self.piece(pos))
+        }))
+    }
+
+    /// Returns an iterator of every position that does not contain air, along with the piece
+    /// there. Positions are visited column by column, from the bottom of each column up.
+    pub fn occupied(&self) -> impl Iterator<Item=(Pos<W, H>, P)> + '_ {
+        (0..W).flat_map(move |x| (0..H).map(move |y| (Pos::new(x, y), self.pieces[x][y])))
+            .filter(|&(_, piece)| piece != P::AIR)
+    }
+
+    /// Gets the fraction of positions on the board that do not contain air, from 0.0 for an
+    /// empty board to 1.0 for a full board. A board with no positions is treated as empty.
+    pub fn fill_ratio(&self) -> f64 {
+        match W * H {
+            0 => 0.0,
+            total => self.occupied().count() as f64 / total as f64
+        }
+    }
 
+    /// Creates a bitboard with a bit set for every position that contains air.
+    pub fn air_mask(&self) -> BitBoard<W, H> {
+        let mut mask = BitBoard::new();
+
+        for (x, col) in self.pieces.iter().enumerate() {
+            for (y, &piece) in col.iter().enumerate() {
+                mask.set(Pos::new(x, y), piece == P::AIR);
             }
         }
 
-        intervals
+        mask
     }
 
-    /// Gets the row air interval that contains the given point, if any.
+    /// Creates a bitboard with a bit set for every position whose piece has the given match
+    /// type, meaning the piece and the match type's piece have a non-air intersection.
     ///
     /// # Arguments
     ///
-    /// * `intervals` - intervals for each row
-    /// * `x` - x-coordinate of the point to find the interval of
-    /// * `y` - y-coordinate of the point to find the interval of
-    fn row_air_interval(intervals: &mut [Vec<RowAirInterval>; H], x: usize, y: usize) -> Option<&mut RowAirInterval> {
-        let interval_index = intervals[y].binary_search_by(|interval| {
-            if interval.begin_x > x {
-                return Greater;
+    /// * `match_type` - the match type to find
+    pub fn type_mask(&self, match_type: P::MatchType) -> BitBoard<W, H> {
+        let type_piece: P = match_type.into();
+        let mut mask = BitBoard::new();
+
+        for (x, col) in self.pieces.iter().enumerate() {
+            for (y, &piece) in col.iter().enumerate() {
+                mask.set(Pos::new(x, y), (type_piece & piece) != P::AIR);
             }
+        }
 
-            if interval.end_x < x {
-                return Less;
+        mask
+    }
+
+    /// Finds every position where `len` consecutive pieces with the given match type begin,
+    /// going right or going up. A run longer than `len` therefore has several of these
+    /// positions, not just its leftmost or bottom piece. Runs are found with bitboard
+    /// operations, which is much faster than checking [`crate::MatchPattern`]s for simple
+    /// lines, but barriers do not break runs. Positions are returned row by row from the bottom
+    /// of the board up, and a position that starts both kinds of run is returned once.
+    ///
+    /// # Arguments
+    ///
+    /// * `match_type` - the match type all pieces in a run must have
+    /// * `len` - the number of consecutive pieces to look for
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is zero.
+    pub fn find_runs(&self, match_type: P::MatchType, len: usize) -> Vec<Pos<W, H>> {
+        if len == 0 {
+            panic!("Tried to find runs of length zero");
+        }
+
+        let mask = self.type_mask(match_type);
+
+        let run_starts = |dir: Direction| {
+            let mut starts = mask.clone();
+            let mut shifted = mask.clone();
+
+            for _ in 1..len {
+                shifted = shifted.shift(dir);
+                starts = starts.and(&shifted);
             }
 
-            Equal
-        }).ok()?;
+            starts
+        };
 
-        Some(&mut intervals[y][interval_index])
+        run_starts(Direction::West)
+            .or(&run_starts(Direction::South))
+            .iter()
+            .collect()
     }
 
-    /// Gets the column air interval that contains the given point, if any.
+    /// Gets every distinct piece on the board, not including air. Requires pieces to be
+    /// hashable, which [`Piece`] does not require on its own.
+    pub fn present_types(&self) -> HashSet<P> where P: Hash {
+        self.pieces.iter()
+            .flat_map(|col| col.iter().copied())
+            .filter(|&piece| piece != P::AIR)
+            .collect()
+    }
+
+    /// Checks whether there is a barrier between two positions.
     ///
     /// # Arguments
     ///
-    /// * `intervals` - intervals for each column
-    /// * `x` - x-coordinate of the point to find the interval of
-    /// * `y` - y-coordinate of the point to find the interval of
-    fn col_air_interval(intervals: &mut [Vec<ColAirInterval>; W], x: usize, y: usize) -> Option<&mut ColAirInterval> {
-        let interval_index = intervals[x].binary_search_by(|interval| {
-            if interval.begin_y > y {
-                return Greater;
-            }
+    /// * `first` - first position to check whether there is a barrier between
+    /// * `second` - second position to check whether there is a barrier between
+    pub fn has_barrier_between(&self, first: Pos<W, H>, second: Pos<W, H>) -> bool {
+        if BoardState::<P, W, H>::vertically_adjacent(first, second) {
+            let x = first.x();
+            let barrier_index = BoardState::<P, W, H>::horizontal_barrier_index(first, second);
+            return self.horizontal_barriers[x][barrier_index]
+        } else if BoardState::<P, W, H>::horizontally_adjacent(first, second) {
+            let y = first.y();
+            let barrier_index = BoardState::<P, W, H>::vertical_barrier_index(first, second);
+            return self.vertical_barriers[y][barrier_index]
+        }
 
-            if interval.end_y < y {
-                return Less;
+        false
+    }
+
+    /// Checks whether there is a barrier between a position and its neighbor in the given
+    /// direction. Returns false if the neighbor would be outside the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - position to check from
+    /// * `dir` - direction of the neighbor to check against
+    pub fn has_barrier_toward(&self, pos: Pos<W, H>, dir: Direction) -> bool {
+        pos.adjacent(dir)
+            .map(|neighbor| self.has_barrier_between(pos, neighbor))
+            .unwrap_or(false)
+    }
+
+    /// Sets whether there is a barrier between two positions. The positions must be directly
+    /// adjacent in the vertical or the horizontal direction. There cannot be barriers between
+    /// diagonally adjacent positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - first position to set a barrier between
+    /// * `second` - second position to set a barrier between
+    /// * `has_barrier` - whether there should be a barrier between the two positions
+    pub fn set_barrier_between(&mut self, first: Pos<W, H>, second: Pos<W, H>, has_barrier: bool) -> Result<(), BoardError<W, H>> {
+        if BoardState::<P, W, H>::vertically_adjacent(first, second) {
+            let x = first.x();
+            let barrier_index = BoardState::<P, W, H>::horizontal_barrier_index(first, second);
+            self.horizontal_barriers[x][barrier_index] = has_barrier;
+            return Ok(());
+        } else if BoardState::<P, W, H>::horizontally_adjacent(first, second) {
+            let y = first.y();
+            let barrier_index = BoardState::<P, W, H>::vertical_barrier_index(first, second);
+            self.vertical_barriers[y][barrier_index] = has_barrier;
+            return Ok(());
+        }
+        
+        Err(NonAdjacent(first, second))
+    }
+
+    /// Counts the consecutive pieces with the given match type, starting at `from` and moving
+    /// in the given direction. The run ends at the first piece without the match type, at a
+    /// barrier, or at the edge of the board. Returns zero if the piece at `from` does not have
+    /// the match type.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - the position where the run starts
+    /// * `dir` - the direction to move in from `from`
+    /// * `match_type` - the match type all pieces in the run must have
+    pub fn run_length(&self, from: Pos<W, H>, dir: Direction, match_type: P::MatchType) -> usize {
+        let type_piece: P = match_type.into();
+        let matches_type = |pos: Pos<W, H>| (type_piece & self.piece(pos)) != P::AIR;
+
+        if !matches_type(from) {
+            return 0;
+        }
+
+        let mut length = 1;
+        let mut pos = from;
+
+        while let Ok(next_pos) = pos.adjacent(dir) {
+            if self.has_barrier_between(pos, next_pos) || !matches_type(next_pos) {
+                break;
             }
 
-            Equal
-        }).ok()?;
+            length += 1;
+            pos = next_pos;
+        }
 
-        Some(&mut intervals[x][interval_index])
+        length
     }
 
-    /// Finds the closest empty space to the given column in the given row, in either direction.
+    /// Returns an iterator of every pair of orthogonally adjacent positions where both pieces
+    /// have the given match type and no barrier is between them. Each pair is returned once,
+    /// with the left or lower position first.
     ///
     /// # Arguments
     ///
-    /// * `x` - column to search from
-    /// * `y` - row to search in
-    /// `air_by_row` - count of empty spaces in each row
+    /// * `match_type` - the match type both pieces in a pair must have
+    pub fn adjacent_pairs(&self, match_type: P::MatchType) -> impl Iterator<Item=(Pos<W, H>, Pos<W, H>)> + '_ {
+        let type_piece: P = match_type.into();
+        let matches_type = move |pos: Pos<W, H>| (type_piece & self.piece(pos)) != P::AIR;
+
+        (0..W).flat_map(move |x| (0..H).map(move |y| Pos::new(x, y)))
+            .filter(move |&pos| matches_type(pos))
+            .flat_map(move |pos| [Direction::East, Direction::North].into_iter()
+                .filter_map(move |dir| pos.adjacent(dir).ok())
+                .filter(move |&next_pos| matches_type(next_pos) && !self.has_barrier_between(pos, next_pos))
+                .map(move |next_pos| (pos, next_pos)))
+    }
+
+    /// Returns the positions directly above, below, right of, and left of the given position
+    /// that are on the board, in that order, along with the pieces at those positions.
     ///
-    /// # Panics
+    /// # Arguments
     ///
-    /// Panics if `air_by_row` indicates there is air in the row, but none could be found.
-    fn closest_air_in_row(&self, x: usize, y: usize, air_by_row: &mut [Vec<RowAirInterval>; H]) -> Option<usize> {
-        let interval = BoardState::<P, W, H>::row_air_interval(air_by_row, x, y)?;
+    /// * `pos` - position to get the neighbors of
+    pub fn neighbors(&self, pos: Pos<W, H>) -> impl Iterator<Item=(Pos<W, H>, P)> + '_ {
+        [Direction::North, Direction::South, Direction::East, Direction::West].into_iter()
+            .filter_map(move |dir| pos.adjacent(dir).ok())
+            .map(|neighbor| (neighbor, self.piece(neighbor)))
+    }
 
-        if interval.air_count == 0 {
-            return None;
-        }
+    /// Returns the same neighbors as [`BoardState::neighbors`], except for those separated
+    /// from the given position by a barrier.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - position to get the neighbors of
+    pub fn unblocked_neighbors(&self, pos: Pos<W, H>) -> impl Iterator<Item=(Pos<W, H>, P)> + '_ {
+        self.neighbors(pos).filter(move |&(neighbor, _)| !self.has_barrier_between(pos, neighbor))
+    }
 
-        for diff in 1..W {
-            if diff <= x - interval.begin_x && self.pieces[x - diff][y] == P::AIR {
-                return Some(x - diff);
+    /// Finds the closest position that contains air, counting steps between adjacent positions
+    /// that are not separated by a barrier. Steps can pass through positions with any piece.
+    /// Returns `from` itself if it contains air. If several positions are equally close, one
+    /// of them is returned. Returns `None` if barriers keep every empty position out of reach.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - position to start searching from
+    pub fn nearest_air(&self, from: Pos<W, H>) -> Option<Pos<W, H>> {
+        let mut visited: [[bool; H]; W] = [[false; H]; W];
+        let mut queue = VecDeque::new();
+
+        visited[from.x()][from.y()] = true;
+        queue.push_back(from);
+
+        while let Some(pos) = queue.pop_front() {
+            if self.piece(pos) == P::AIR {
+                return Some(pos);
             }
 
-            if diff <= interval.end_x - x && self.pieces[x + diff][y] == P::AIR {
-                return Some(x + diff);
+            for (next, _) in self.unblocked_neighbors(pos) {
+                if !visited[next.x()][next.y()] {
+                    visited[next.x()][next.y()] = true;
+                    queue.push_back(next);
+                }
             }
         }
 
-        panic!("air_by_row claims {} air spaces in row {}, but none found", interval.air_count, y)
+        None
     }
 
-    /// Rotates the given row by one so that the piece in `start_x` moves into the space in `end_x`.
-    /// Returns the swaps made to perform the rotation.
+    /// Finds a shortest path that a piece could walk from one position to another, moving one
+    /// step at a time through air without crossing barriers. The path includes both the start and
+    /// the end. The start may hold any piece, but every other position on the path must be air.
+    /// Returns `None` if there is no such path.
     ///
     /// # Arguments
     ///
-    /// * `y` - index of the row to rotate
-    /// * `start_x` - x-coordinate of the piece that will move to `end_x`
-    /// * `end_x` - destination of the piece at `start_x` after the rotation
-    fn rotate_row(&mut self, y: usize, start_x: usize, end_x: usize) -> Vec<(Pos<W, H>, Pos<W, H>)> {
-        let mut moves = Vec::new();
-
-        match start_x <= end_x {
-            true => {
-                for x in start_x..end_x {
-                    let left_pos =  Pos::new(x, y);
-                    let right_pos = Pos::new(x + 1, y);
-
-                    self.swap(left_pos, right_pos);
-                    moves.push((left_pos, right_pos));
+    /// * `from` - position where the path starts
+    /// * `to` - position where the path ends
+    pub fn path(&self, from: Pos<W, H>, to: Pos<W, H>) -> Option<Vec<Pos<W, H>>> {
+        let mut previous: [[Option<Pos<W, H>>; H]; W] = from_fn(|_| from_fn(|_| None));
+        let mut visited: [[bool; H]; W] = [[false; H]; W];
+        let mut queue = VecDeque::new();
+
+        visited[from.x()][from.y()] = true;
+        queue.push_back(from);
+
+        while let Some(pos) = queue.pop_front() {
+            if pos == to {
+                let mut path = vec![pos];
+                let mut current = pos;
+
+                while let Some(previous_pos) = previous[current.x()][current.y()] {
+                    path.push(previous_pos);
+                    current = previous_pos;
                 }
-            },
-            false => {
-                for x in ((end_x + 1)..=start_x).rev() {
-                    let left_pos =  Pos::new(x - 1, y);
-                    let right_pos = Pos::new(x, y);
 
-                    self.swap(left_pos, right_pos);
-                    moves.push((left_pos, right_pos));
+                path.reverse();
+                return Some(path);
+            }
+
+            for (next, piece) in self.unblocked_neighbors(pos) {
+                if !visited[next.x()][next.y()] && piece == P::AIR {
+                    visited[next.x()][next.y()] = true;
+                    previous[next.x()][next.y()] = Some(pos);
+                    queue.push_back(next);
                 }
             }
         }
 
-        moves
+        None
     }
 
-}
+    /// Mirrors the board from left to right, so that the piece in the first column moves to the
+    /// last column and vice versa. Barriers move with the pieces.
+    pub fn flip_horizontal(&mut self) {
+        self.pieces.reverse();
+        self.horizontal_barriers.reverse();
 
-impl<P: Piece, const W: usize, const H: usize> Default for BoardState<P, W, H> {
-    fn default() -> Self {
-        Self::new()
+        for row_barriers in self.vertical_barriers.iter_mut() {
+
+            // The barrier between x and x + 1 moves between W - x - 2 and W - x - 1
+            if W > 1 {
+                row_barriers[..(W - 1)].reverse();
+            }
+
+        }
     }
-}
 
-/// Counts which x positions in a column contain air, between begin_x and end_x (inclusive).
-struct RowAirInterval {
-    begin_x: usize,
-    end_x: usize,
-    air_count: usize
-}
+    /// Mirrors the board from top to bottom, so that the piece in the bottom row moves to the
+    /// top row and vice versa. Barriers move with the pieces.
+    pub fn flip_vertical(&mut self) {
+        for col_pieces in self.pieces.iter_mut() {
+            col_pieces.reverse();
+        }
 
-/// Describes what y positions in a column contain air, between begin_y and end_y (inclusive).
-struct ColAirInterval {
+        self.vertical_barriers.reverse();
+
+        for col_barriers in self.horizontal_barriers.iter_mut() {
+
+            // The barrier between y and y + 1 moves between H - y - 2 and H - y - 1
+            if H > 1 {
+                col_barriers[..(H - 1)].reverse();
+            }
+
+        }
+    }
+
+    /// Checks whether the pieces on the board are a mirror image of themselves across an axis.
+    /// Across the horizontal axis, every row must match the row the same distance from the
+    /// other edge, like after [`BoardState::flip_vertical`]. Across the vertical axis, every
+    /// column must match the opposite column, like after [`BoardState::flip_horizontal`].
+    /// Barriers are not checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - the axis to mirror the pieces across
+    pub fn is_symmetric(&self, axis: Axis) -> bool {
+        (0..W).all(|x| (0..H).all(|y| {
+            let (mirror_x, mirror_y) = match axis {
+                Axis::Horizontal => (x, H - 1 - y),
+                Axis::Vertical => (W - 1 - x, y)
+            };
+
+            self.pieces[x][y] == self.pieces[mirror_x][mirror_y]
+        }))
+    }
+
+    /// Moves every piece on the board by the same distance in one direction. Pieces pushed past
+    /// the edge of the board are removed, and the positions left behind are filled with the
+    /// given piece. Barriers stay where they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - the direction to move every piece
+    /// * `amount` - how many positions to move every piece
+    /// * `fill` - the piece to put in the positions left behind
+    pub fn shift(&mut self, dir: Direction, amount: usize, fill: P) {
+        let previous = self.pieces;
+
+        for x in 0..W {
+            for y in 0..H {
+                let source = match dir {
+                    Direction::North => y.checked_sub(amount).map(|source_y| (x, source_y)),
+                    Direction::South => y.checked_add(amount).filter(|&source_y| source_y < H)
+                        .map(|source_y| (x, source_y)),
+                    Direction::East => x.checked_sub(amount).map(|source_x| (source_x, y)),
+                    Direction::West => x.checked_add(amount).filter(|&source_x| source_x < W)
+                        .map(|source_x| (source_x, y))
+                };
+
+                self.pieces[x][y] = source.map(|(source_x, source_y)| previous[source_x][source_y])
+                    .unwrap_or(fill);
+            }
+        }
+    }
+
+    /// Finds every difference between this board and another board as a [`Changeset`].
+    /// Applying the changeset to this board with [`BoardState::apply`] makes it equal to the
+    /// other board.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the board that the changeset should produce
+    pub fn changeset(&self, other: &BoardState<P, W, H>) -> Changeset<P, W, H> {
+        let mut changeset = Changeset {
+            pieces: Vec::new(),
+            horizontal_barriers: Vec::new(),
+            vertical_barriers: Vec::new()
+        };
+
+        for x in 0..W {
+            for y in 0..H {
+                let pos = Pos::new(x, y);
+
+                if self.piece(pos) != other.piece(pos) {
+                    changeset.pieces.push((pos, other.piece(pos)));
+                }
+
+                if let Ok(above) = pos.adjacent(Direction::North) {
+                    let has_barrier = other.has_barrier_between(pos, above);
+                    if self.has_barrier_between(pos, above) != has_barrier {
+                        changeset.horizontal_barriers.push((pos, has_barrier));
+                    }
+                }
+
+                if let Ok(right) = pos.adjacent(Direction::East) {
+                    let has_barrier = other.has_barrier_between(pos, right);
+                    if self.has_barrier_between(pos, right) != has_barrier {
+                        changeset.vertical_barriers.push((pos, has_barrier));
+                    }
+                }
+            }
+        }
+
+        changeset
+    }
+
+    /// Makes every change in a [`Changeset`] to this board. Changes that already match the
+    /// board have no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `changeset` - the changes to make
+    pub fn apply(&mut self, changeset: &Changeset<P, W, H>) {
+        for &(pos, piece) in changeset.pieces.iter() {
+            self.set_piece(pos, piece);
+        }
+
+        for &(pos, has_barrier) in changeset.horizontal_barriers.iter() {
+            if let Ok(above) = pos.adjacent(Direction::North) {
+                self.set_barrier_between(pos, above, has_barrier).unwrap();
+            }
+        }
+
+        for &(pos, has_barrier) in changeset.vertical_barriers.iter() {
+            if let Ok(right) = pos.adjacent(Direction::East) {
+                self.set_barrier_between(pos, right, has_barrier).unwrap();
+            }
+        }
+    }
+
+    /// Copies the positions of every barrier on this board, without any pieces. Useful for
+    /// saving a level's fixed walls separately from the pieces on the board.
+    pub fn export_barriers(&self) -> BarrierLayout<W, H> {
+        let horizontal_barriers = (0..W)
+            .flat_map(|x| (0..H).map(move |y| (x, y)))
+            .filter(|&(x, y)| self.horizontal_barriers[x][y])
+            .map(|(x, y)| Pos::new(x, y))
+            .collect();
+
+        let vertical_barriers = (0..W)
+            .flat_map(|x| (0..H).map(move |y| (x, y)))
+            .filter(|&(x, y)| self.vertical_barriers[y][x])
+            .map(|(x, y)| Pos::new(x, y))
+            .collect();
+
+        BarrierLayout { horizontal_barriers, vertical_barriers }
+    }
+
+    /// Replaces every barrier on this board with the barriers in the given layout. Pieces are
+    /// not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - the barriers to place on this board
+    pub fn import_barriers(&mut self, layout: &BarrierLayout<W, H>) {
+        self.horizontal_barriers = [[false; H]; W];
+        self.vertical_barriers = [[false; W]; H];
+
+        for &pos in layout.horizontal_barriers.iter() {
+            if let Ok(above) = pos.adjacent(Direction::North) {
+                self.set_barrier_between(pos, above, true).unwrap();
+            }
+        }
+
+        for &pos in layout.vertical_barriers.iter() {
+            if let Ok(right) = pos.adjacent(Direction::East) {
+                self.set_barrier_between(pos, right, true).unwrap();
+            }
+        }
+    }
+
+    /// Converts this board into its compact form, which only contains pieces that are not air
+    /// and barriers.
+    pub fn to_sparse(&self) -> SparseBoard<P, W, H> {
+        let pieces = (0..W)
+            .flat_map(|x| (0..H).map(move |y| Pos::new(x, y)))
+            .filter(|&pos| self.piece(pos) != P::AIR)
+            .map(|pos| (pos, self.piece(pos)))
+            .collect();
+
+        let BarrierLayout { horizontal_barriers, vertical_barriers } = self.export_barriers();
+
+        SparseBoard { pieces, horizontal_barriers, vertical_barriers }
+    }
+
+    /// Creates a board from its compact form. All positions not listed in the compact form
+    /// contain air. Barriers that would be along the top or right edge of the board are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `sparse` - the compact form of the board
+    pub fn from_sparse(sparse: &SparseBoard<P, W, H>) -> BoardState<P, W, H> {
+        let mut board = BoardState::new();
+
+        for &(pos, piece) in sparse.pieces.iter() {
+            board.set_piece(pos, piece);
+        }
+
+        for &pos in sparse.horizontal_barriers.iter() {
+            if let Ok(above) = pos.adjacent(Direction::North) {
+                board.set_barrier_between(pos, above, true).unwrap();
+            }
+        }
+
+        for &pos in sparse.vertical_barriers.iter() {
+            if let Ok(right) = pos.adjacent(Direction::East) {
+                board.set_barrier_between(pos, right, true).unwrap();
+            }
+        }
+
+        board
+    }
+
+    /// Checks if two positions are horizontally adjacent.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - first position to compare
+    /// * `second` - second position to compare
+    fn horizontally_adjacent(first: Pos<W, H>, second: Pos<W, H>) -> bool {
+        usize::abs_diff(first.x(), second.x()) == 1
+            && usize::abs_diff(first.y(), second.y()) == 0
+    }
+
+    /// Checks if two positions are vertically adjacent.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - first position to compare
+    /// * `second` - second position to compare
+    fn vertically_adjacent(first: Pos<W, H>, second: Pos<W, H>) -> bool {
+        usize::abs_diff(first.x(), second.x()) == 0
+            && usize::abs_diff(first.y(), second.y()) == 1
+    }
+
+    /// Retrieves the index of a horizontal barrier within an array of horizontal barriers for
+    /// the same column.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - first position that the barrier exists between
+    /// * `second` - second position that the barrier exists between
+    fn horizontal_barrier_index(first: Pos<W, H>, second: Pos<W, H>) -> usize {
+        first.y().min(second.y())
+    }
+
+    /// Retrieves the index of a vertical barrier within an array of vertical barriers for
+    /// the same row.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - first position that the barrier exists between
+    /// * `second` - second position that the barrier exists between
+    fn vertical_barrier_index(first: Pos<W, H>, second: Pos<W, H>) -> usize {
+        first.x().min(second.x())
+    }
+
+    /// Moves every piece with a negative [`Piece::weight`] straight up, as described in
+    /// [`BoardState::apply_gravity_to_board`]. Returns the swaps that were made, in the order in
+    /// which they were applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `frozen` - the positions that gravity skips
+    fn apply_rising_gravity(&mut self, frozen: &BitBoard<W, H>) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let mut moves = Vec::new();
+
+        // Handle the highest pieces first so that each rising piece stops below the ones above it
+        for x in 0..W {
+            for y in (0..H).rev() {
+                let mut pos = Pos::new(x, y);
+
+                let piece = self.piece(pos);
+                if piece == P::AIR || piece.weight() >= 0 || frozen.get(pos) {
+                    continue;
+                }
+
+                loop {
+                    let reachable = self.reachable_above(pos, frozen);
+                    let next_piece_pos = reachable.iter()
+                        .copied()
+                        .find(|&above| self.piece(above) != P::AIR);
+
+                    match next_piece_pos {
+
+                        // Pass through falling pieces one at a time so that they keep their order
+                        Some(above) if self.piece(above).weight() > 0 => {
+                            self.swap(pos, above);
+                            moves.push((pos, above));
+                            pos = above;
+                        },
+
+                        _ => {
+                            let highest_air = reachable.into_iter()
+                                .take_while(|&above| self.piece(above) == P::AIR)
+                                .last();
+
+                            if let Some(air_pos) = highest_air {
+                                self.swap(pos, air_pos);
+                                moves.push((pos, air_pos));
+                            }
+
+                            break;
+                        }
+
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// Finds the positions above the given position that a rising piece could move through,
+    /// from lowest to highest, stopping at a barrier, a piece that stays in place, a frozen
+    /// position, or the top of the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to look above
+    /// * `frozen` - the positions that gravity skips
+    fn reachable_above(&self, pos: Pos<W, H>, frozen: &BitBoard<W, H>) -> Vec<Pos<W, H>> {
+        let mut reachable = Vec::new();
+        let mut current = pos;
+
+        while let Ok(above) = current.adjacent(Direction::North) {
+            let piece = self.piece(above);
+            let is_fixed = frozen.get(above) || (piece != P::AIR && piece.weight() == 0);
+
+            if is_fixed || self.has_barrier_between(current, above) {
+                break;
+            }
+
+            reachable.push(above);
+            current = above;
+        }
+
+        reachable
+    }
+
+    /// Checks whether a position keeps its contents while other pieces fall, which is true for
+    /// frozen positions and for pieces that do not fall according to [`Piece::weight`].
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    /// * `frozen` - the positions that gravity skips
+    fn holds_in_place(&self, pos: Pos<W, H>, frozen: &BitBoard<W, H>) -> bool {
+        let piece = self.piece(pos);
+        frozen.get(pos) || (piece != P::AIR && piece.weight() <= 0)
+    }
+
+    /// Checks whether falling pieces cannot move between two adjacent positions, either because
+    /// of a barrier or because one of the positions holds in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - first position to check
+    /// * `second` - second position to check
+    /// * `frozen` - the positions that gravity skips
+    fn blocks_falling_between(&self, first: Pos<W, H>, second: Pos<W, H>, frozen: &BitBoard<W, H>) -> bool {
+        self.has_barrier_between(first, second)
+            || self.holds_in_place(first, frozen)
+            || self.holds_in_place(second, frozen)
+    }
+
+    /// Scans the whole board to find air intervals for each column.
+    ///
+    /// # Arguments
+    ///
+    /// * `frozen` - the positions that gravity skips, which never count as air
+    fn scan_row_air(&self, frozen: &BitBoard<W, H>) -> [Vec<RowAirInterval>; H] {
+        let mut intervals: [Vec<RowAirInterval>; H] = from_fn(|_| Vec::new());
+
+        for (y, row_intervals) in intervals.iter_mut().enumerate() {
+            let mut begin_x = 0;
+            let mut air_count = 0;
+
+            for x in 0..W {
+                let pos = Pos::new(x, y);
+
+                if self.pieces[x][y] == P::AIR && !frozen.get(pos) {
+                    air_count += 1;
+                }
+
+                let right_pos = pos.adjacent(Direction::East);
+                let barrier_right = right_pos.map(|p| self.blocks_falling_between(pos, p, frozen))
+                    .unwrap_or(false);
+
+                // End an interval at a barrier or at the top of the board
+                if barrier_right || x == W - 1 {
+                    let interval = RowAirInterval {
+                        begin_x,
+                        end_x: x,
+                        air_count,
+                    };
+
+                    row_intervals.push(interval);
+
+                    begin_x = x + 1;
+                    air_count = 0;
+                }
+
+            }
+        }
+
+        intervals
+    }
+
+    /// Scans the whole board to find air intervals for each column..
+    ///
+    /// # Arguments
+    ///
+    /// * `frozen` - the positions that gravity skips, which never count as air
+    fn scan_col_air(&self, frozen: &BitBoard<W, H>) -> [Vec<ColAirInterval>; W] {
+        let mut intervals: [Vec<ColAirInterval>; W] = from_fn(|_| Vec::new());
+
+        for (x, col_intervals) in intervals.iter_mut().enumerate() {
+            let mut begin_y = 0;
+            let mut air_ys = BTreeSet::new();
+
+            for y in 0..H {
+                let pos = Pos::new(x, y);
+
+                if self.pieces[x][y] == P::AIR && !frozen.get(pos) {
+                    air_ys.insert(y);
+                }
+
+                let pos_above = pos.adjacent(Direction::North);
+                let barrier_above = pos_above.map(|p| self.blocks_falling_between(pos, p, frozen))
+                    .unwrap_or(false);
+
+                // End an interval at a barrier or at the top of the board
+                if barrier_above || y == H - 1 {
+                    let interval = ColAirInterval {
+                        begin_y,
+                        end_y: y,
+                        air_ys: air_ys.clone(),
+                    };
+
+                    col_intervals.push(interval);
+
+                    begin_y = y + 1;
+                    air_ys.clear();
+                }
+
+            }
+        }
+
+        intervals
+    }
+
+    /// Gets the row air interval that contains the given point, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `intervals` - intervals for each row
+    /// * `x` - x-coordinate of the point to find the interval of
+    /// * `y` - y-coordinate of the point to find the interval of
+    fn row_air_interval(intervals: &mut [Vec<RowAirInterval>; H], x: usize, y: usize) -> Option<&mut RowAirInterval> {
+        let interval_index = intervals[y].binary_search_by(|interval| {
+            if interval.begin_x > x {
+                return Greater;
+            }
+
+            if interval.end_x < x {
+                return Less;
+            }
+
+            Equal
+        }).ok()?;
+
+        Some(&mut intervals[y][interval_index])
+    }
+
+    /// Gets the column air interval that contains the given point, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `intervals` - intervals for each column
+    /// * `x` - x-coordinate of the point to find the interval of
+    /// * `y` - y-coordinate of the point to find the interval of
+    fn col_air_interval(intervals: &mut [Vec<ColAirInterval>; W], x: usize, y: usize) -> Option<&mut ColAirInterval> {
+        let interval_index = intervals[x].binary_search_by(|interval| {
+            if interval.begin_y > y {
+                return Greater;
+            }
+
+            if interval.end_y < y {
+                return Less;
+            }
+
+            Equal
+        }).ok()?;
+
+        Some(&mut intervals[x][interval_index])
+    }
+
+    /// Finds the closest empty space to the given column in the given row, in either direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - column to search from
+    /// * `y` - row to search in
+    /// * `air_by_row` - count of empty spaces in each row
+    /// * `bias` - the direction to prefer when empty spaces on both sides are equally close
+    ///
+    /// # Panics
+    ///
+    /// Panics if `air_by_row` indicates there is air in the row, but none could be found.
+    fn closest_air_in_row(&self, x: usize, y: usize, air_by_row: &mut [Vec<RowAirInterval>; H],
+                          bias: GravityBias) -> Option<usize> {
+        let interval = BoardState::<P, W, H>::row_air_interval(air_by_row, x, y)?;
+
+        if interval.air_count == 0 {
+            return None;
+        }
+
+        for diff in 1..W {
+            let left_x = match diff <= x - interval.begin_x && self.pieces[x - diff][y] == P::AIR {
+                true => Some(x - diff),
+                false => None
+            };
+            let right_x = match diff <= interval.end_x - x && self.pieces[x + diff][y] == P::AIR {
+                true => Some(x + diff),
+                false => None
+            };
+
+            let closest_x = match bias {
+                GravityBias::Left => left_x.or(right_x),
+                GravityBias::Right => right_x.or(left_x)
+            };
+            if closest_x.is_some() {
+                return closest_x;
+            }
+        }
+
+        panic!("air_by_row claims {} air spaces in row {}, but none found", interval.air_count, y)
+    }
+
+    /// Reverses the order of the pieces in part of a column and records the swaps made.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - index of the column to reverse
+    /// * `begin_y` - first y-coordinate of the section to reverse (inclusive)
+    /// * `end_y` - last y-coordinate of the section to reverse (exclusive)
+    /// * `moves` - the list of swaps to add to
+    fn reverse_column_section(&mut self, x: usize, begin_y: usize, end_y: usize, moves: &mut Vec<(Pos<W, H>, Pos<W, H>)>) {
+        let section_len = end_y - begin_y;
+
+        for offset in 0..(section_len / 2) {
+            let lower_pos = Pos::new(x, begin_y + offset);
+            let upper_pos = Pos::new(x, end_y - offset - 1);
+
+            self.swap(lower_pos, upper_pos);
+            moves.push((lower_pos, upper_pos));
+        }
+    }
+
+    /// Rotates the given row by one so that the piece in `start_x` moves into the space in `end_x`.
+    /// Returns the swaps made to perform the rotation.
+    ///
+    /// # Arguments
+    ///
+    /// * `y` - index of the row to rotate
+    /// * `start_x` - x-coordinate of the piece that will move to `end_x`
+    /// * `end_x` - destination of the piece at `start_x` after the rotation
+    fn rotate_row(&mut self, y: usize, start_x: usize, end_x: usize) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let mut moves = Vec::new();
+
+        match start_x <= end_x {
+            true => {
+                for x in start_x..end_x {
+                    let left_pos =  Pos::new(x, y);
+                    let right_pos = Pos::new(x + 1, y);
+
+                    self.swap(left_pos, right_pos);
+                    moves.push((left_pos, right_pos));
+                }
+            },
+            false => {
+                for x in ((end_x + 1)..=start_x).rev() {
+                    let left_pos =  Pos::new(x - 1, y);
+                    let right_pos = Pos::new(x, y);
+
+                    self.swap(left_pos, right_pos);
+                    moves.push((left_pos, right_pos));
+                }
+            }
+        }
+
+        moves
+    }
+
+}
+
+impl<P: Piece, const W: usize, const H: usize> Default for BoardState<P, W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A compact form of a [`BoardState`] that only stores pieces that are not air and the
+/// positions of barriers. Much smaller than a full board when serialized if most of the
+/// board is empty.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SparseBoard<P, const WIDTH: usize, const HEIGHT: usize> {
+
+    /// Every position that does not contain air, with its piece
+    pieces: Vec<(Pos<WIDTH, HEIGHT>, P)>,
+
+    /// Positions with a barrier between them and the position directly above
+    horizontal_barriers: Vec<Pos<WIDTH, HEIGHT>>,
+
+    /// Positions with a barrier between them and the position directly to the right
+    vertical_barriers: Vec<Pos<WIDTH, HEIGHT>>
+
+}
+
+/// The barriers on a board without its pieces, found by [`BoardState::export_barriers`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BarrierLayout<const WIDTH: usize, const HEIGHT: usize> {
+
+    /// Positions with a barrier between them and the position directly above
+    horizontal_barriers: Vec<Pos<WIDTH, HEIGHT>>,
+
+    /// Positions with a barrier between them and the position directly to the right
+    vertical_barriers: Vec<Pos<WIDTH, HEIGHT>>
+
+}
+
+/// The differences between two boards, found by [`BoardState::changeset`]. Only the new value
+/// of each changed piece or barrier is stored, so a changeset is usually much smaller than a
+/// full board when serialized.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Changeset<P, const WIDTH: usize, const HEIGHT: usize> {
+
+    /// Every position whose piece changed, with its new piece
+    pieces: Vec<(Pos<WIDTH, HEIGHT>, P)>,
+
+    /// Positions whose barrier with the position directly above changed, with whether there is
+    /// now a barrier
+    horizontal_barriers: Vec<(Pos<WIDTH, HEIGHT>, bool)>,
+
+    /// Positions whose barrier with the position directly to the right changed, with whether
+    /// there is now a barrier
+    vertical_barriers: Vec<(Pos<WIDTH, HEIGHT>, bool)>
+
+}
+
+impl<P, const W: usize, const H: usize> Changeset<P, W, H> {
+
+    /// Checks whether this changeset has no changes, which means the two boards were equal.
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty() && self.horizontal_barriers.is_empty() && self.vertical_barriers.is_empty()
+    }
+
+}
+
+/// Counts which x positions in a column contain air, between begin_x and end_x (inclusive).
+struct RowAirInterval {
+    begin_x: usize,
+    end_x: usize,
+    air_count: usize
+}
+
+/// Describes what y positions in a column contain air, between begin_y and end_y (inclusive).
+struct ColAirInterval {
     begin_y: usize,
     end_y: usize,
     air_ys: BTreeSet<usize>
 }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashSet;
-    use std::ops::BitAnd;
-    use crate::{BoardState, Col, Piece, Pos};
+/// The fields of a [`BoardState`] as they are serialized, before the barriers are validated.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "BoardState", bound(deserialize = "P: serde::Deserialize<'de>"))]
+struct UncheckedBoardState<P, const WIDTH: usize, const HEIGHT: usize> {
+    #[serde(with = "grid_serde")]
+    pieces: [[P; HEIGHT]; WIDTH],
+
+    #[serde(with = "grid_serde")]
+    horizontal_barriers: [[bool; HEIGHT]; WIDTH],
+
+    #[serde(with = "grid_serde")]
+    vertical_barriers: [[bool; WIDTH]; HEIGHT]
+}
+
+#[cfg(feature = "serde")]
+impl<'de, P: Piece + serde::Deserialize<'de>, const W: usize, const H: usize> serde::Deserialize<'de> for BoardState<P, W, H> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let unchecked = UncheckedBoardState::<P, W, H>::deserialize(deserializer)?;
+
+        BoardState::try_from_parts(unchecked.pieces, unchecked.horizontal_barriers, unchecked.vertical_barriers)
+            .map_err(|err| serde::de::Error::custom(format!("invalid board: {:?}", err)))
+    }
+}
+
+/// Serializes two-dimensional arrays as nested sequences, since serde only implements its
+/// traits for arrays up to a fixed length.
+#[cfg(feature = "serde")]
+mod grid_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::de::Error;
+    use serde::ser::SerializeSeq;
+
+    /// Serializes a two-dimensional array as a sequence of sequences.
+    ///
+    /// # Arguments
+    ///
+    /// * `grid` - the array to serialize
+    /// * `serializer` - the serializer to write the array to
+    pub fn serialize<T: Serialize, S: Serializer, const OUTER: usize, const INNER: usize>(
+        grid: &[[T; INNER]; OUTER],
+        serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(OUTER))?;
+
+        for line in grid {
+            seq.serialize_element(&line[..])?;
+        }
+
+        seq.end()
+    }
+
+    /// Deserializes a two-dimensional array from a sequence of sequences, returning an error
+    /// if either dimension does not match the array's dimensions.
+    ///
+    /// # Arguments
+    ///
+    /// * `deserializer` - the deserializer to read the array from
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>, const OUTER: usize, const INNER: usize>(
+        deserializer: D
+    ) -> Result<[[T; INNER]; OUTER], D::Error> {
+        let lines: Vec<Vec<T>> = Vec::deserialize(deserializer)?;
+        let line_count = lines.len();
+
+        let arrays = lines.into_iter().map(|line| {
+            let len = line.len();
+            <[T; INNER]>::try_from(line).map_err(|_| D::Error::invalid_length(len, &"a line matching the board size"))
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        <[[T; INNER]; OUTER]>::try_from(arrays)
+            .map_err(|_| D::Error::invalid_length(line_count, &"a line count matching the board size"))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::clone_on_copy)]
+mod tests {
+    use std::array::from_fn;
+    use std::collections::HashSet;
+    use std::ops::BitAnd;
+    use crate::{Axis, BoardError, BoardState, Col, Direction, GravityBias, MergeGravityResult, Piece, Pos};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    enum TestPiece {
+        #[default]
+        Air = 0b00,
+        First = 0b01,
+        Second = 0b10
+    }
+
+    impl From<u8> for TestPiece {
+        fn from(value: u8) -> Self {
+            match value {
+                0 => TestPiece::First,
+                1 => TestPiece::Second,
+                _ => TestPiece::Air
+            }
+        }
+    }
+
+    impl BitAnd for TestPiece {
+        type Output = Self;
+
+        fn bitand(self, rhs: Self) -> Self::Output {
+            match self as u8 & rhs as u8 {
+                0b00 => TestPiece::Air,
+                0b01 => TestPiece::First,
+                0b10 => TestPiece::Second,
+                _ => panic!("impossible")
+            }
+        }
+    }
+
+    impl Piece for TestPiece {
+        type MatchType = u8;
+        const AIR: Self = Self::Air;
+
+        fn merge(self, other: Self) -> Option<Self> {
+            match (self, other) {
+                (TestPiece::First, TestPiece::First) => Some(TestPiece::Second),
+                _ => None
+            }
+        }
+    }
+
+    fn moves_produce_board<const W: usize, const H: usize>(moves: &Vec<(Pos<W, H>, Pos<W, H>)>,
+                                                           start: &mut BoardState<TestPiece, W, H>,
+                                                           end: &BoardState<TestPiece, W, H>) -> bool {
+        for (first, second) in moves {
+            start.swap(*first, *second);
+        }
+
+        for x in 0..W {
+            for y in 0..H {
+                let pos = Pos::new(x, y);
+                if start.piece(pos) != end.piece(pos) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    #[test]
+    fn get_piece_zero_zero_default_retrieved() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn get_piece_never_set_default_retrieved() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(5, 10)));
+    }
+
+    #[test]
+    fn try_piece_in_bounds_some() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(14, 15), TestPiece::Second);
+
+        assert_eq!(Some(TestPiece::Second), board.try_piece(14, 15));
+        assert_eq!(Some(TestPiece::Air), board.try_piece(0, 0));
+    }
+
+    #[test]
+    fn try_piece_out_of_bounds_none() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+
+        assert!(board.try_piece(15, 4).is_none());
+        assert!(board.try_piece(1, 16).is_none());
+        assert!(board.try_piece(usize::MAX, usize::MAX).is_none());
+    }
+
+    #[test]
+    fn swap_adjacent_swapped() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let piece1 = TestPiece::First;
+        let piece2 = TestPiece::Second;
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece2);
+
+        board.swap(Pos::new(1, 2), Pos::new(1, 3));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 3)));
+    }
+
+    #[test]
+    fn swap_non_adjacent_swapped() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let piece1 = TestPiece::First;
+        let piece2 = TestPiece::Second;
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(14, 15), piece2);
+
+        board.swap(Pos::new(1, 2), Pos::new(14, 15));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+        assert_eq!(piece1, board.piece(Pos::new(14, 15)));
+    }
+
+    #[test]
+    fn swap_self_no_change() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let piece1 = TestPiece::First;
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        board.swap(Pos::new(1, 2), Pos::new(1, 2));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn swap_reporting_returns_swapped_pieces() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let piece1 = TestPiece::First;
+        let piece2 = TestPiece::Second;
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece2);
+
+        assert_eq!((piece2, piece1), board.swap_reporting(Pos::new(1, 2), Pos::new(1, 3)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 3)));
+    }
+
+    #[test]
+    fn swap_reporting_self_returns_same_piece() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let piece1 = TestPiece::First;
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        assert_eq!((piece1, piece1), board.swap_reporting(Pos::new(1, 2), Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn set_piece_none_previous_default_returned() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let piece1 = TestPiece::First;
+
+        assert_eq!(TestPiece::default(), board.set_piece(Pos::new(1, 2), piece1));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn set_piece_one_previous_old_returned() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let piece1 = TestPiece::First;
+        let piece2 = TestPiece::Second;
+
+        assert_eq!(TestPiece::default(), board.set_piece(Pos::new(1, 2), piece1));
+        assert_eq!(piece1, board.set_piece(Pos::new(1, 2), piece2));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn set_piece_duplicate_old_returned() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let piece1 = TestPiece::First;
+
+        assert_eq!(TestPiece::default(), board.set_piece(Pos::new(1, 2), piece1));
+        assert_eq!(piece1, board.set_piece(Pos::new(1, 2), piece1));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn has_barrier_barriers_unset_defaults_false() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        for x in 0..14 {
+            for y in 0..15 {
+                assert!(!board.has_barrier_between(Pos::new(x, y), Pos::new(x, y + 1)));
+                assert!(!board.has_barrier_between(Pos::new(x, y), Pos::new(x + 1, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn has_barrier_barrier_set_true() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        for x in 0..14 {
+            for y in 0..15 {
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), true).unwrap();
+                assert!(board.has_barrier_between(Pos::new(x, y), Pos::new(x, y + 1)));
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), false).unwrap();
+
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), true).unwrap();
+                assert!(board.has_barrier_between(Pos::new(x, y), Pos::new(x + 1, y)));
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), false).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn has_barrier_barrier_set_twice_true() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        for x in 0..14 {
+            for y in 0..15 {
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), true).unwrap();
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), true).unwrap();
+                assert!(board.has_barrier_between(Pos::new(x, y), Pos::new(x, y + 1)));
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), false).unwrap();
+
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), true).unwrap();
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), true).unwrap();
+                assert!(board.has_barrier_between(Pos::new(x, y), Pos::new(x + 1, y)));
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), false).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn has_barrier_barrier_overwritten_false() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        for x in 0..14 {
+            for y in 0..15 {
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), true).unwrap();
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), false).unwrap();
+                assert!(!board.has_barrier_between(Pos::new(x, y), Pos::new(x, y + 1)));
+
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), true).unwrap();
+                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), false).unwrap();
+                assert!(!board.has_barrier_between(Pos::new(x, y), Pos::new(x + 1, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn set_barrier_horizontally_separated_err() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert!(board.set_barrier_between(Pos::new(1, 2), Pos::new(3, 2), true).is_err());
+    }
+
+    #[test]
+    fn set_barrier_vertically_separated_err() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert!(board.set_barrier_between(Pos::new(1, 2), Pos::new(1, 4), true).is_err());
+    }
+
+    #[test]
+    fn set_barrier_diagonally_adjacent_panics() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert!(board.set_barrier_between(Pos::new(1, 2), Pos::new(2, 3), true).is_err());
+    }
+
+    #[test]
+    fn has_barrier_horizontally_separated_false() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert!(!board.has_barrier_between(Pos::new(1, 2), Pos::new(3, 2)));
+    }
+
+    #[test]
+    fn has_barrier_vertically_separated_false() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert!(!board.has_barrier_between(Pos::new(1, 2), Pos::new(1, 4)));
+    }
+
+    #[test]
+    fn has_barrier_diagonally_adjacent_false() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert!(!board.has_barrier_between(Pos::new(1, 2), Pos::new(2, 3)));
+    }
+
+    #[test]
+    fn run_length_start_not_matching_zero() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(2, 3), TestPiece::Second);
+        board.set_piece(Pos::new(3, 3), TestPiece::First);
+
+        assert_eq!(0, board.run_length(Pos::new(2, 3), Direction::East, 0));
+    }
+
+    #[test]
+    fn run_length_stops_at_different_piece() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(2, 3), TestPiece::First);
+        board.set_piece(Pos::new(2, 4), TestPiece::First);
+        board.set_piece(Pos::new(2, 5), TestPiece::Second);
+        board.set_piece(Pos::new(2, 6), TestPiece::First);
+
+        assert_eq!(2, board.run_length(Pos::new(2, 3), Direction::North, 0));
+    }
+
+    #[test]
+    fn run_length_stops_at_barrier() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(2, 3), TestPiece::First);
+        board.set_piece(Pos::new(3, 3), TestPiece::First);
+        board.set_piece(Pos::new(4, 3), TestPiece::First);
+        board.set_barrier_between(Pos::new(3, 3), Pos::new(4, 3), true).unwrap();
+
+        assert_eq!(2, board.run_length(Pos::new(2, 3), Direction::East, 0));
+        assert_eq!(1, board.run_length(Pos::new(4, 3), Direction::West, 0));
+    }
+
+    #[test]
+    fn run_length_stops_at_edge() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 3), TestPiece::First);
+        board.set_piece(Pos::new(1, 3), TestPiece::First);
+        board.set_piece(Pos::new(2, 3), TestPiece::First);
+
+        assert_eq!(3, board.run_length(Pos::new(2, 3), Direction::West, 0));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip_equal() {
+        let mut board: BoardState<TestPiece, 4, 3> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 2), TestPiece::Second);
+        board.set_barrier_between(Pos::new(1, 1), Pos::new(1, 2), true).unwrap();
+        board.set_barrier_between(Pos::new(2, 0), Pos::new(3, 0), true).unwrap();
+
+        let serialized = serde_json::to_string(&board).unwrap();
+        let deserialized: BoardState<TestPiece, 4, 3> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(board, deserialized);
+        assert!(deserialized.has_barrier_between(Pos::new(1, 1), Pos::new(1, 2)));
+        assert!(deserialized.has_barrier_between(Pos::new(2, 0), Pos::new(3, 0)));
+    }
+
+    #[test]
+    fn barriers_valid_set_barriers_true() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_barrier_between(Pos::new(3, 14), Pos::new(3, 15), true).unwrap();
+        board.set_barrier_between(Pos::new(13, 2), Pos::new(14, 2), true).unwrap();
+
+        assert!(board.barriers_valid());
+    }
+
+    #[test]
+    fn barriers_valid_stray_edge_barrier_false() {
+        let mut top_board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        top_board.horizontal_barriers[3][15] = true;
+        assert!(!top_board.barriers_valid());
+
+        let mut right_board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        right_board.vertical_barriers[2][14] = true;
+        assert!(!right_board.barriers_valid());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_stray_edge_barrier_err() {
+        let mut board: BoardState<TestPiece, 4, 3> = BoardState::new();
+        board.horizontal_barriers[1][2] = true;
 
-    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
-    enum TestPiece {
-        #[default]
-        Air = 0b00,
-        First = 0b01,
-        Second = 0b10
+        let serialized = serde_json::to_string(&board).unwrap();
+
+        assert!(serde_json::from_str::<BoardState<TestPiece, 4, 3>>(&serialized).is_err());
     }
 
-    impl From<u8> for TestPiece {
-        fn from(value: u8) -> Self {
-            match value {
-                0 => TestPiece::First,
-                1 => TestPiece::Second,
-                _ => TestPiece::Air
-            }
-        }
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_wrong_size_err() {
+        let board: BoardState<TestPiece, 4, 3> = BoardState::new();
+
+        let serialized = serde_json::to_string(&board).unwrap();
+
+        assert!(serde_json::from_str::<BoardState<TestPiece, 3, 3>>(&serialized).is_err());
+        assert!(serde_json::from_str::<BoardState<TestPiece, 4, 4>>(&serialized).is_err());
     }
 
-    impl BitAnd for TestPiece {
-        type Output = Self;
+    #[test]
+    fn sparse_round_trip_equal() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(7, 3), TestPiece::Second);
+        board.set_piece(Pos::new(14, 15), TestPiece::First);
+        board.set_barrier_between(Pos::new(4, 4), Pos::new(5, 4), true).unwrap();
 
-        fn bitand(self, rhs: Self) -> Self::Output {
-            match self as u8 & rhs as u8 {
-                0b00 => TestPiece::Air,
-                0b01 => TestPiece::First,
-                0b10 => TestPiece::Second,
-                _ => panic!("impossible")
-            }
-        }
+        let sparse = board.to_sparse();
+
+        assert_eq!(board, BoardState::from_sparse(&sparse));
     }
 
-    impl Piece for TestPiece {
-        type MatchType = u8;
-        const AIR: Self = Self::Air;
+    #[test]
+    fn sparse_only_stores_non_air() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(7, 3), TestPiece::Second);
+        board.set_barrier_between(Pos::new(2, 9), Pos::new(2, 10), true).unwrap();
+
+        let sparse = board.to_sparse();
+
+        assert_eq!(vec![(Pos::new(7, 3), TestPiece::Second)], sparse.pieces);
+        assert_eq!(vec![Pos::new(2, 9)], sparse.horizontal_barriers);
+        assert!(sparse.vertical_barriers.is_empty());
     }
 
-    fn moves_produce_board<const W: usize, const H: usize>(moves: &Vec<(Pos<W, H>, Pos<W, H>)>,
-                                                           start: &mut BoardState<TestPiece, W, H>,
-                                                           end: &BoardState<TestPiece, W, H>) -> bool {
-        for (first, second) in moves {
-            start.swap(*first, *second);
+    #[test]
+    fn flip_horizontal_twice_unchanged() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 7), TestPiece::Second);
+        board.set_barrier_between(Pos::new(4, 4), Pos::new(5, 4), true).unwrap();
+        board.set_barrier_between(Pos::new(6, 2), Pos::new(6, 3), true).unwrap();
+
+        let start_board = board;
+        board.flip_horizontal();
+        assert_ne!(start_board, board);
+        board.flip_horizontal();
+
+        assert_eq!(start_board, board);
+    }
+
+    #[test]
+    fn flip_horizontal_pieces_and_barriers_mirrored() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 7), TestPiece::Second);
+        board.set_barrier_between(Pos::new(4, 4), Pos::new(5, 4), true).unwrap();
+        board.set_barrier_between(Pos::new(6, 2), Pos::new(6, 3), true).unwrap();
+
+        board.flip_horizontal();
+
+        assert_eq!(TestPiece::First, board.piece(Pos::new(14, 0)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(11, 7)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(0, 0)));
+        assert!(board.has_barrier_between(Pos::new(9, 4), Pos::new(10, 4)));
+        assert!(!board.has_barrier_between(Pos::new(4, 4), Pos::new(5, 4)));
+        assert!(board.has_barrier_between(Pos::new(8, 2), Pos::new(8, 3)));
+        assert!(!board.has_barrier_between(Pos::new(6, 2), Pos::new(6, 3)));
+    }
+
+    #[test]
+    fn flip_vertical_twice_unchanged() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 7), TestPiece::Second);
+        board.set_barrier_between(Pos::new(4, 4), Pos::new(5, 4), true).unwrap();
+        board.set_barrier_between(Pos::new(6, 2), Pos::new(6, 3), true).unwrap();
+
+        let start_board = board;
+        board.flip_vertical();
+        assert_ne!(start_board, board);
+        board.flip_vertical();
+
+        assert_eq!(start_board, board);
+    }
+
+    #[test]
+    fn flip_vertical_pieces_and_barriers_mirrored() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 7), TestPiece::Second);
+        board.set_barrier_between(Pos::new(4, 4), Pos::new(5, 4), true).unwrap();
+        board.set_barrier_between(Pos::new(6, 2), Pos::new(6, 3), true).unwrap();
+
+        board.flip_vertical();
+
+        assert_eq!(TestPiece::First, board.piece(Pos::new(0, 15)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 8)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(0, 0)));
+        assert!(board.has_barrier_between(Pos::new(4, 11), Pos::new(5, 11)));
+        assert!(!board.has_barrier_between(Pos::new(4, 4), Pos::new(5, 4)));
+        assert!(board.has_barrier_between(Pos::new(6, 12), Pos::new(6, 13)));
+        assert!(!board.has_barrier_between(Pos::new(6, 2), Pos::new(6, 3)));
+    }
+
+    #[test]
+    fn refill_from_east_fills_rows_up_to_pieces() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+
+        // Pieces packed against the west edge, as if gravity pulled them sideways
+        for x in 0..4 {
+            board.set_piece(Pos::new(x, 0), TestPiece::First);
         }
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        board.set_barrier_between(Pos::new(9, 2), Pos::new(10, 2), true).unwrap();
 
-        for x in 0..W {
-            for y in 0..H {
-                let pos = Pos::new(x, y);
-                if start.piece(pos) != end.piece(pos) {
-                    return false;
-                }
-            }
+        let filled = board.refill_from(Direction::East, |_| TestPiece::Second);
+
+        assert_eq!(Pos::new(14, 0), filled[0]);
+        assert_eq!(Pos::new(4, 0), filled[10]);
+        assert_eq!(11 + 14 + 5 + 15 * 13, filled.len());
+
+        for x in 0..4 {
+            assert_eq!(TestPiece::First, board.piece(Pos::new(x, 0)));
         }
+        for x in 1..15 {
+            assert_eq!(TestPiece::Second, board.piece(Pos::new(x, 1)));
+        }
+        for x in 0..10 {
+            assert_eq!(TestPiece::Air, board.piece(Pos::new(x, 2)));
+        }
+        for x in 10..15 {
+            assert_eq!(TestPiece::Second, board.piece(Pos::new(x, 2)));
+        }
+    }
 
-        true
+    #[test]
+    fn refill_from_north_fills_columns_from_top() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(2, 13), TestPiece::First);
+
+        let filled = board.refill_from(Direction::North, |pos| match pos.x() {
+            2 => TestPiece::Second,
+            _ => TestPiece::First
+        });
+
+        assert_eq!(16 * 14 + 2, filled.len());
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 15)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 14)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(2, 12)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 0)));
     }
 
     #[test]
-    fn get_piece_zero_zero_default_retrieved() {
+    #[cfg(feature = "rand")]
+    fn refill_weighted_single_choice_fills_with_choice() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(2, 0), TestPiece::First);
+        board.set_barrier_between(Pos::new(4, 9), Pos::new(4, 10), true).unwrap();
+
+        let mut rng = rand::rngs::mock::StepRng::new(0, 7);
+        let filled = board.refill_weighted(&mut rng, &[(TestPiece::Second, 3), (TestPiece::First, 0)]);
+
+        assert_eq!(15 * 16 - 1 - 10, filled.len());
+        for (pos, piece) in filled {
+            assert_eq!(TestPiece::Second, piece);
+            assert_eq!(TestPiece::Second, board.piece(pos));
+        }
+        assert_eq!(TestPiece::First, board.piece(Pos::new(2, 0)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(4, 9)));
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn refill_weighted_chooses_by_weight() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+
+        let mut rng = rand::rngs::mock::StepRng::new(0, u64::MAX / 97);
+        let filled = board.refill_weighted(&mut rng, &[(TestPiece::First, 1), (TestPiece::Second, 1)]);
+
+        assert!(filled.iter().any(|&(_, piece)| piece == TestPiece::First));
+        assert!(filled.iter().any(|&(_, piece)| piece == TestPiece::Second));
+    }
+
+    #[test]
+    fn occupied_skips_air() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(5, 3), TestPiece::Second);
+        board.set_piece(Pos::new(5, 1), TestPiece::First);
+        board.set_piece(Pos::new(14, 15), TestPiece::Second);
+
+        assert_eq!(vec![
+            (Pos::new(0, 0), TestPiece::First),
+            (Pos::new(5, 1), TestPiece::First),
+            (Pos::new(5, 3), TestPiece::Second),
+            (Pos::new(14, 15), TestPiece::Second)
+        ], board.occupied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn occupied_empty_board_empty() {
         let board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        assert_eq!(TestPiece::Air, board.piece(Pos::new(0, 0)));
+        assert_eq!(0, board.occupied().count());
     }
 
     #[test]
-    fn get_piece_never_set_default_retrieved() {
+    fn present_types_two_kinds_both_returned() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(5, 3), TestPiece::Second);
+        board.set_piece(Pos::new(14, 15), TestPiece::First);
+
+        assert_eq!(HashSet::from([TestPiece::First, TestPiece::Second]), board.present_types());
+    }
+
+    #[test]
+    fn present_types_empty_board_empty() {
         let board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        assert_eq!(TestPiece::Air, board.piece(Pos::new(5, 10)));
+        assert!(board.present_types().is_empty());
     }
 
     #[test]
-    fn swap_adjacent_swapped() {
+    fn apply_diagonal_gravity_ledge_slides_into_gap() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        let piece1 = TestPiece::First;
-        let piece2 = TestPiece::Second;
+        board.set_piece(Pos::new(5, 4), TestPiece::First);
+        board.set_barrier_between(Pos::new(5, 3), Pos::new(5, 4), true).unwrap();
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), piece2);
+        let mut start_board = board;
+        let moves = board.apply_diagonal_gravity();
 
-        board.swap(Pos::new(1, 2), Pos::new(1, 3));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 3)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(4, 0)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(5, 4)));
+        assert_eq!(vec![(Pos::new(5, 4), Pos::new(4, 3)), (Pos::new(4, 3), Pos::new(4, 0))], moves);
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
     }
 
     #[test]
-    fn swap_non_adjacent_swapped() {
+    fn apply_diagonal_gravity_stack_spreads() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        let piece1 = TestPiece::First;
-        let piece2 = TestPiece::Second;
+        board.set_piece(Pos::new(5, 0), TestPiece::First);
+        board.set_piece(Pos::new(5, 1), TestPiece::Second);
+        board.set_piece(Pos::new(5, 2), TestPiece::First);
+        board.set_piece(Pos::new(4, 0), TestPiece::First);
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(14, 15), piece2);
+        let mut start_board = board;
+        let moves = board.apply_diagonal_gravity();
 
-        board.swap(Pos::new(1, 2), Pos::new(14, 15));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
-        assert_eq!(piece1, board.piece(Pos::new(14, 15)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(4, 0)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(5, 0)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(6, 0)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(5, 1)));
+        assert_eq!(4, board.occupied().count());
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
     }
 
     #[test]
-    fn swap_self_no_change() {
+    fn apply_diagonal_gravity_barrier_beside_blocks_slide() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        let piece1 = TestPiece::First;
+        board.set_piece(Pos::new(5, 0), TestPiece::First);
+        board.set_piece(Pos::new(5, 1), TestPiece::Second);
+        board.set_barrier_between(Pos::new(4, 1), Pos::new(5, 1), true).unwrap();
+        board.set_barrier_between(Pos::new(6, 0), Pos::new(6, 1), true).unwrap();
 
-        board.set_piece(Pos::new(1, 2), piece1);
+        assert!(board.apply_diagonal_gravity().is_empty());
+    }
 
-        board.swap(Pos::new(1, 2), Pos::new(1, 2));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+    #[test]
+    fn apply_merge_gravity_two_equal_pieces_merged() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(3, 2), TestPiece::First);
+        board.set_piece(Pos::new(3, 6), TestPiece::First);
+
+        let MergeGravityResult { moves, merges } = board.apply_merge_gravity();
+
+        assert_eq!(vec![(Pos::new(3, 2), Pos::new(3, 0))], moves);
+        assert_eq!(vec![(Pos::new(3, 6), Pos::new(3, 0))], merges);
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 0)));
+        for y in 1..16 {
+            assert_eq!(TestPiece::Air, board.piece(Pos::new(3, y)));
+        }
     }
 
     #[test]
-    fn set_piece_none_previous_default_returned() {
+    fn apply_merge_gravity_merged_piece_not_merged_again() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        let piece1 = TestPiece::First;
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 3), TestPiece::First);
+        board.set_piece(Pos::new(3, 4), TestPiece::First);
+        board.set_piece(Pos::new(3, 6), TestPiece::Second);
 
-        assert_eq!(TestPiece::default(), board.set_piece(Pos::new(1, 2), piece1));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        let MergeGravityResult { moves, merges } = board.apply_merge_gravity();
+
+        assert_eq!(vec![(Pos::new(3, 4), Pos::new(3, 1)), (Pos::new(3, 6), Pos::new(3, 2))], moves);
+        assert_eq!(vec![(Pos::new(3, 3), Pos::new(3, 0))], merges);
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 0)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 1)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 2)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(3, 3)));
     }
 
     #[test]
-    fn set_piece_one_previous_old_returned() {
+    fn apply_merge_gravity_resting_pieces_not_merged() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        let piece1 = TestPiece::First;
-        let piece2 = TestPiece::Second;
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 1), TestPiece::First);
+        board.set_piece(Pos::new(3, 4), TestPiece::Second);
 
-        assert_eq!(TestPiece::default(), board.set_piece(Pos::new(1, 2), piece1));
-        assert_eq!(piece1, board.set_piece(Pos::new(1, 2), piece2));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+        let MergeGravityResult { moves, merges } = board.apply_merge_gravity();
+
+        assert_eq!(vec![(Pos::new(3, 4), Pos::new(3, 2))], moves);
+        assert!(merges.is_empty());
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 0)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 1)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 2)));
+    }
+
+    #[test]
+    fn apply_merge_gravity_barrier_prevents_merge() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 5), TestPiece::First);
+        board.set_barrier_between(Pos::new(3, 0), Pos::new(3, 1), true).unwrap();
+
+        let MergeGravityResult { moves, merges } = board.apply_merge_gravity();
+
+        assert_eq!(vec![(Pos::new(3, 5), Pos::new(3, 1))], moves);
+        assert!(merges.is_empty());
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 0)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 1)));
+    }
+
+    #[test]
+    fn has_barrier_toward_each_direction() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        let pos = Pos::new(5, 5);
+
+        board.set_barrier_between(pos, Pos::new(5, 6), true).unwrap();
+        assert!(board.has_barrier_toward(pos, Direction::North));
+        assert!(board.has_barrier_toward(Pos::new(5, 6), Direction::South));
+        assert!(!board.has_barrier_toward(pos, Direction::South));
+        assert!(!board.has_barrier_toward(pos, Direction::East));
+        assert!(!board.has_barrier_toward(pos, Direction::West));
+
+        board.set_barrier_between(pos, Pos::new(5, 4), true).unwrap();
+        assert!(board.has_barrier_toward(pos, Direction::South));
+
+        board.set_barrier_between(pos, Pos::new(6, 5), true).unwrap();
+        assert!(board.has_barrier_toward(pos, Direction::East));
+        assert!(board.has_barrier_toward(Pos::new(6, 5), Direction::West));
+
+        board.set_barrier_between(pos, Pos::new(4, 5), true).unwrap();
+        assert!(board.has_barrier_toward(pos, Direction::West));
+    }
+
+    #[test]
+    fn has_barrier_toward_off_board_false() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+
+        assert!(!board.has_barrier_toward(Pos::new(0, 0), Direction::South));
+        assert!(!board.has_barrier_toward(Pos::new(0, 0), Direction::West));
+        assert!(!board.has_barrier_toward(Pos::new(14, 15), Direction::North));
+        assert!(!board.has_barrier_toward(Pos::new(14, 15), Direction::East));
+    }
+
+    #[test]
+    fn neighbors_corner_two_neighbors() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+
+        assert_eq!(vec![
+            (Pos::new(0, 1), TestPiece::Second),
+            (Pos::new(1, 0), TestPiece::First)
+        ], board.neighbors(Pos::new(0, 0)).collect::<Vec<_>>());
+
+        assert_eq!(vec![
+            (Pos::new(14, 14), TestPiece::Air),
+            (Pos::new(13, 15), TestPiece::Air)
+        ], board.neighbors(Pos::new(14, 15)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn neighbors_center_four_neighbors() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(5, 6), TestPiece::First);
+        board.set_piece(Pos::new(5, 4), TestPiece::Second);
+        board.set_piece(Pos::new(6, 5), TestPiece::Second);
+        board.set_barrier_between(Pos::new(5, 5), Pos::new(5, 6), true).unwrap();
+
+        assert_eq!(vec![
+            (Pos::new(5, 6), TestPiece::First),
+            (Pos::new(5, 4), TestPiece::Second),
+            (Pos::new(6, 5), TestPiece::Second),
+            (Pos::new(4, 5), TestPiece::Air)
+        ], board.neighbors(Pos::new(5, 5)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn unblocked_neighbors_barrier_skipped() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(5, 6), TestPiece::First);
+        board.set_piece(Pos::new(5, 4), TestPiece::Second);
+        board.set_barrier_between(Pos::new(5, 5), Pos::new(5, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(4, 5), Pos::new(5, 5), true).unwrap();
+
+        assert_eq!(vec![
+            (Pos::new(5, 4), TestPiece::Second),
+            (Pos::new(6, 5), TestPiece::Air)
+        ], board.unblocked_neighbors(Pos::new(5, 5)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn path_around_barrier_wall_found() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        for y in 0..3 {
+            board.set_barrier_between(Pos::new(0, y), Pos::new(1, y), true).unwrap();
+        }
+
+        let path = board.path(Pos::new(0, 0), Pos::new(1, 0)).unwrap();
+
+        assert_eq!(vec![
+            Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2), Pos::new(0, 3),
+            Pos::new(1, 3), Pos::new(1, 2), Pos::new(1, 1), Pos::new(1, 0)
+        ], path);
+    }
+
+    #[test]
+    fn path_same_pos_only_start() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert_eq!(Some(vec![Pos::new(4, 4)]), board.path(Pos::new(4, 4), Pos::new(4, 4)));
+    }
+
+    #[test]
+    fn path_blocked_by_pieces_none() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_barrier_between(Pos::new(0, 0), Pos::new(0, 1), true).unwrap();
+
+        assert_eq!(None, board.path(Pos::new(0, 0), Pos::new(5, 5)));
+    }
+
+    #[test]
+    fn path_target_occupied_none() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(5, 5), TestPiece::Second);
+
+        assert_eq!(None, board.path(Pos::new(0, 0), Pos::new(5, 5)));
+    }
+
+    #[test]
+    fn column_height_filled_to_ten() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        for y in 0..10 {
+            board.set_piece(Pos::new(3, y), TestPiece::First);
+        }
+        board.set_barrier_between(Pos::new(3, 12), Pos::new(3, 13), true).unwrap();
+
+        assert_eq!(10, board.column_height(Col::new(3)));
+        assert!(board.is_over_threshold(Col::new(3), 9));
+        assert!(!board.is_over_threshold(Col::new(3), 10));
+    }
+
+    #[test]
+    fn column_height_gap_uses_topmost_piece() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(3, 7), TestPiece::Second);
+
+        assert_eq!(8, board.column_height(Col::new(3)));
+        assert_eq!(0, board.column_height(Col::new(4)));
+    }
+
+    #[test]
+    fn column_height_full_column_board_height() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        for y in 0..16 {
+            board.set_piece(Pos::new(0, y), TestPiece::First);
+        }
+
+        assert_eq!(16, board.column_height(Col::new(0)));
+        assert!(board.is_over_threshold(Col::new(0), 15));
     }
 
     #[test]
-    fn set_piece_duplicate_old_returned() {
+    fn landing_position_ledge_lands_on_barrier() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        let piece1 = TestPiece::First;
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
+        board.set_barrier_between(Pos::new(3, 6), Pos::new(3, 7), true).unwrap();
 
-        assert_eq!(TestPiece::default(), board.set_piece(Pos::new(1, 2), piece1));
-        assert_eq!(piece1, board.set_piece(Pos::new(1, 2), piece1));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(Some(Pos::new(3, 7)), board.landing_position(Col::new(3), TestPiece::First));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(3, 15)));
     }
 
     #[test]
-    fn has_barrier_barriers_unset_defaults_false() {
+    fn landing_position_empty_column_bottom() {
         let board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        for x in 0..14 {
-            for y in 0..15 {
-                assert!(!board.has_barrier_between(Pos::new(x, y), Pos::new(x, y + 1)));
-                assert!(!board.has_barrier_between(Pos::new(x, y), Pos::new(x + 1, y)));
-            }
-        }
+        assert_eq!(Some(Pos::new(3, 0)), board.landing_position(Col::new(3), TestPiece::First));
     }
 
     #[test]
-    fn has_barrier_barrier_set_true() {
+    fn landing_position_on_piece_slides_below() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        for x in 0..14 {
-            for y in 0..15 {
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), true).unwrap();
-                assert!(board.has_barrier_between(Pos::new(x, y), Pos::new(x, y + 1)));
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), false).unwrap();
+        board.set_piece(Pos::new(5, 0), TestPiece::First);
 
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), true).unwrap();
-                assert!(board.has_barrier_between(Pos::new(x, y), Pos::new(x + 1, y)));
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), false).unwrap();
-            }
-        }
+        let mut expected_board = board;
+        expected_board.set_piece(Pos::new(5, 15), TestPiece::Second);
+        expected_board.apply_gravity_to_board();
+
+        assert_eq!(Some(Pos::new(5, 0)), board.landing_position(Col::new(5), TestPiece::First));
+        assert_eq!(TestPiece::Second, expected_board.piece(Pos::new(5, 0)));
     }
 
     #[test]
-    fn has_barrier_barrier_set_twice_true() {
+    fn landing_position_full_column_none() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        for x in 0..14 {
-            for y in 0..15 {
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), true).unwrap();
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), true).unwrap();
-                assert!(board.has_barrier_between(Pos::new(x, y), Pos::new(x, y + 1)));
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), false).unwrap();
-
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), true).unwrap();
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), true).unwrap();
-                assert!(board.has_barrier_between(Pos::new(x, y), Pos::new(x + 1, y)));
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), false).unwrap();
-            }
+        for y in 0..16 {
+            board.set_piece(Pos::new(2, y), TestPiece::First);
         }
+
+        assert_eq!(None, board.landing_position(Col::new(2), TestPiece::First));
     }
 
     #[test]
-    fn has_barrier_barrier_overwritten_false() {
-        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        for x in 0..14 {
-            for y in 0..15 {
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), true).unwrap();
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x, y + 1), false).unwrap();
-                assert!(!board.has_barrier_between(Pos::new(x, y), Pos::new(x, y + 1)));
+    fn from_array_pieces_match_input() {
+        let pieces: [[TestPiece; 16]; 15] = from_fn(|x| from_fn(|y| match (x + y) % 3 {
+            0 => TestPiece::Air,
+            1 => TestPiece::First,
+            _ => TestPiece::Second
+        }));
 
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), true).unwrap();
-                board.set_barrier_between(Pos::new(x, y), Pos::new(x + 1, y), false).unwrap();
-                assert!(!board.has_barrier_between(Pos::new(x, y), Pos::new(x + 1, y)));
+        let board = BoardState::from_array(pieces);
+
+        for (x, col_pieces) in pieces.iter().enumerate() {
+            for (y, &piece) in col_pieces.iter().enumerate() {
+                let pos = Pos::new(x, y);
+                assert_eq!(piece, board.piece(pos));
+                assert!(!board.has_barrier_toward(pos, Direction::North));
+                assert!(!board.has_barrier_toward(pos, Direction::East));
             }
         }
     }
 
     #[test]
-    fn set_barrier_horizontally_separated_err() {
-        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        assert!(board.set_barrier_between(Pos::new(1, 2), Pos::new(3, 2), true).is_err());
-    }
+    fn from_rows_bottom_up_rest_default() {
+        let board: BoardState<TestPiece, 3, 4> = BoardState::from_rows([
+            [TestPiece::First, TestPiece::Air, TestPiece::Second],
+            [TestPiece::Second, TestPiece::First, TestPiece::Air]
+        ]);
 
-    #[test]
-    fn set_barrier_vertically_separated_err() {
-        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        assert!(board.set_barrier_between(Pos::new(1, 2), Pos::new(1, 4), true).is_err());
+        assert_eq!(TestPiece::First, board.piece(Pos::new(0, 0)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(1, 0)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 0)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(0, 1)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 1)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(2, 1)));
+        assert_eq!(0, (0..3).filter(|&x| board.piece(Pos::new(x, 2)) != TestPiece::Air).count());
+        assert_eq!(0, (0..3).filter(|&x| board.piece(Pos::new(x, 3)) != TestPiece::Air).count());
     }
 
     #[test]
-    fn set_barrier_diagonally_adjacent_panics() {
-        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        assert!(board.set_barrier_between(Pos::new(1, 2), Pos::new(2, 3), true).is_err());
+    #[should_panic]
+    fn from_rows_too_many_rows_panics() {
+        BoardState::<TestPiece, 1, 2>::from_rows([[TestPiece::First], [TestPiece::First], [TestPiece::First]]);
     }
 
     #[test]
-    fn has_barrier_horizontally_separated_false() {
-        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        assert!(!board.has_barrier_between(Pos::new(1, 2), Pos::new(3, 2)));
+    fn try_from_parts_valid_parts_constructed() {
+        let mut pieces = [[TestPiece::Air; 16]; 15];
+        pieces[3][4] = TestPiece::First;
+        let mut horizontal_barriers = [[false; 16]; 15];
+        horizontal_barriers[2][14] = true;
+        let mut vertical_barriers = [[false; 15]; 16];
+        vertical_barriers[6][13] = true;
+
+        let board = BoardState::try_from_parts(pieces, horizontal_barriers, vertical_barriers).unwrap();
+
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 4)));
+        assert!(board.has_barrier_between(Pos::new(2, 14), Pos::new(2, 15)));
+        assert!(board.has_barrier_between(Pos::new(13, 6), Pos::new(14, 6)));
     }
 
     #[test]
-    fn has_barrier_vertically_separated_false() {
-        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        assert!(!board.has_barrier_between(Pos::new(1, 2), Pos::new(1, 4)));
+    fn try_from_parts_barrier_above_top_rejected() {
+        let mut horizontal_barriers = [[false; 16]; 15];
+        horizontal_barriers[2][15] = true;
+
+        let result = BoardState::<TestPiece, 15, 16>::try_from_parts(
+            [[TestPiece::Air; 16]; 15],
+            horizontal_barriers,
+            [[false; 15]; 16]
+        );
+
+        assert_eq!(Err(BoardError::OutsideBarrier(Pos::new(2, 15), Direction::North)), result);
     }
 
     #[test]
-    fn has_barrier_diagonally_adjacent_false() {
-        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        assert!(!board.has_barrier_between(Pos::new(1, 2), Pos::new(2, 3)));
+    fn try_from_parts_barrier_past_right_edge_rejected() {
+        let mut vertical_barriers = [[false; 15]; 16];
+        vertical_barriers[6][14] = true;
+
+        let result = BoardState::<TestPiece, 15, 16>::try_from_parts(
+            [[TestPiece::Air; 16]; 15],
+            [[false; 16]; 15],
+            vertical_barriers
+        );
+
+        assert_eq!(Err(BoardError::OutsideBarrier(Pos::new(14, 6), Direction::East)), result);
     }
 
     #[test]
@@ -868,6 +3152,31 @@ mod tests {
         assert_eq!(7, board.surface(Col::new(x)).unwrap());
     }
 
+    #[test]
+    fn floating_pieces_empty_board_none() {
+        let board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert!(board.floating_pieces().is_empty());
+    }
+
+    #[test]
+    fn floating_pieces_supported_and_floating() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 1), TestPiece::Second);
+        board.set_piece(Pos::new(3, 5), TestPiece::First);
+
+        assert_eq!(vec![Pos::new(3, 5)], board.floating_pieces());
+    }
+
+    #[test]
+    fn floating_pieces_on_barrier_not_floating() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(3, 5), TestPiece::First);
+        board.set_barrier_between(Pos::new(3, 4), Pos::new(3, 5), true).unwrap();
+
+        assert!(board.floating_pieces().is_empty());
+    }
+
     #[test]
     fn column_gravity_zero_height_no_exception() {
         let mut board: BoardState<TestPiece, 15, 0> = BoardState::new();
@@ -922,6 +3231,219 @@ mod tests {
         assert_eq!(TestPiece::Air, board.piece(Pos::new(x, 7)));
     }
 
+    #[test]
+    fn column_gravity_multiple_barriers_segments_compact_independently() {
+        let mut board: BoardState<TestPiece, 15, 12> = BoardState::new();
+        let x = 1;
+
+        board.set_piece(Pos::new(x, 2), TestPiece::First);
+        board.set_piece(Pos::new(x, 3), TestPiece::Second);
+        board.set_piece(Pos::new(x, 6), TestPiece::Second);
+        board.set_piece(Pos::new(x, 7), TestPiece::First);
+        board.set_piece(Pos::new(x, 9), TestPiece::First);
+        board.set_piece(Pos::new(x, 11), TestPiece::Second);
+
+        board.set_barrier_between(Pos::new(x, 3), Pos::new(x, 4), true).unwrap();
+        board.set_barrier_between(Pos::new(x, 7), Pos::new(x, 8), true).unwrap();
+
+        assert_eq!(
+            vec![(2, 0), (3, 1), (6, 4), (7, 5), (9, 8), (11, 9)],
+            board.apply_gravity_to_column(Col::new(1))
+        );
+
+        let expected_pieces = [
+            TestPiece::First, TestPiece::Second, TestPiece::Air, TestPiece::Air,
+            TestPiece::Second, TestPiece::First, TestPiece::Air, TestPiece::Air,
+            TestPiece::First, TestPiece::Second, TestPiece::Air, TestPiece::Air
+        ];
+        for (y, piece) in expected_pieces.into_iter().enumerate() {
+            assert_eq!(piece, board.piece(Pos::new(x, y)));
+        }
+    }
+
+    #[test]
+    fn cycle_column_zero_height_no_moves() {
+        let mut board: BoardState<TestPiece, 15, 0> = BoardState::new();
+        assert!(board.cycle_column(Col::new(1), 3).is_empty());
+    }
+
+    #[test]
+    fn cycle_column_shift_by_height_unchanged() {
+        let mut board: BoardState<TestPiece, 15, 8> = BoardState::new();
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 3), TestPiece::Second);
+        board.set_piece(Pos::new(1, 7), TestPiece::First);
+
+        let start_board = board;
+
+        assert!(board.cycle_column(Col::new(1), 8).is_empty());
+        assert!(board.cycle_column(Col::new(1), -16).is_empty());
+        assert_eq!(start_board, board);
+    }
+
+    #[test]
+    fn cycle_column_shift_up_one_wraps() {
+        let mut board: BoardState<TestPiece, 15, 8> = BoardState::new();
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 3), TestPiece::Second);
+        board.set_piece(Pos::new(1, 7), TestPiece::First);
+        board.set_piece(Pos::new(2, 7), TestPiece::Second);
+
+        let mut start_board = board;
+        let moves = board.cycle_column(Col::new(1), 1);
+
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 0)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 1)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(1, 4)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(1, 3)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(1, 7)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 7)));
+
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
+    }
+
+    #[test]
+    fn cycle_column_shift_down_wraps() {
+        let mut board: BoardState<TestPiece, 15, 8> = BoardState::new();
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 1), TestPiece::Second);
+
+        let mut start_board = board;
+        let moves = board.cycle_column(Col::new(1), -2);
+
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 6)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(1, 7)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(1, 0)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(1, 1)));
+
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
+    }
+
+    #[test]
+    fn slide_row_right_swaps_in_order() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(2, 4), TestPiece::First);
+        board.set_piece(Pos::new(3, 4), TestPiece::Second);
+        board.set_piece(Pos::new(5, 4), TestPiece::Second);
+
+        let moves = board.slide_row(4, 2, 5);
+
+        assert_eq!(vec![
+            (Pos::new(2, 4), Pos::new(3, 4)),
+            (Pos::new(3, 4), Pos::new(4, 4)),
+            (Pos::new(4, 4), Pos::new(5, 4))
+        ], moves);
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 4)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(3, 4)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(4, 4)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(5, 4)));
+    }
+
+    #[test]
+    fn slide_row_left_swaps_in_order() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(5, 4), TestPiece::First);
+        board.set_piece(Pos::new(3, 4), TestPiece::Second);
+
+        let moves = board.slide_row(4, 5, 3);
+
+        assert_eq!(vec![
+            (Pos::new(4, 4), Pos::new(5, 4)),
+            (Pos::new(3, 4), Pos::new(4, 4))
+        ], moves);
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 4)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(4, 4)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(5, 4)));
+    }
+
+    #[test]
+    fn slide_row_same_column_no_moves() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        assert!(board.slide_row(4, 5, 5).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn slide_row_row_out_of_bounds_panics() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.slide_row(16, 2, 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn slide_row_column_out_of_bounds_panics() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.slide_row(4, 2, 15);
+    }
+
+    #[test]
+    fn columns_gravity_no_columns_unchanged() {
+        let mut board: BoardState<TestPiece, 15, 8> = BoardState::new();
+        board.set_piece(Pos::new(1, 5), TestPiece::First);
+
+        assert!(board.apply_gravity_to_columns(&[]).is_empty());
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn columns_gravity_only_given_columns_fall() {
+        let mut board: BoardState<TestPiece, 15, 8> = BoardState::new();
+        board.set_piece(Pos::new(1, 5), TestPiece::First);
+        board.set_piece(Pos::new(2, 6), TestPiece::Second);
+        board.set_piece(Pos::new(4, 3), TestPiece::First);
+        board.set_piece(Pos::new(4, 7), TestPiece::Second);
+
+        let moves = board.apply_gravity_to_columns(&[Col::new(4), Col::new(1)]);
+
+        assert_eq!(vec![(4, vec![(3, 0), (7, 1)]), (1, vec![(5, 0)])], moves);
+
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 0)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 6)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(2, 0)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(4, 0)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(4, 1)));
+    }
+
+    #[test]
+    fn gravity_step_moves_one_space() {
+        let mut board: BoardState<TestPiece, 15, 8> = BoardState::new();
+        board.set_piece(Pos::new(1, 5), TestPiece::First);
+        board.set_piece(Pos::new(1, 6), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::First);
+
+        let moves = board.apply_gravity_step();
+
+        assert_eq!(vec![
+            (Pos::new(1, 5), Pos::new(1, 4)),
+            (Pos::new(1, 6), Pos::new(1, 5))
+        ], moves);
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 4)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(1, 5)));
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(1, 6)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(2, 0)));
+    }
+
+    #[test]
+    fn gravity_step_repeated_matches_column_gravity() {
+        let mut board: BoardState<TestPiece, 15, 8> = BoardState::new();
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 2), TestPiece::Second);
+        board.set_piece(Pos::new(1, 5), TestPiece::First);
+        board.set_piece(Pos::new(1, 7), TestPiece::Second);
+
+        let mut expected_board = board;
+        expected_board.apply_gravity_to_column(Col::new(1));
+
+        let mut steps = 0;
+        while !board.apply_gravity_step().is_empty() {
+            steps += 1;
+        }
+
+        assert_eq!(4, steps);
+        assert_eq!(expected_board, board);
+        assert!(board.floating_pieces().is_empty());
+    }
+
     #[test]
     fn board_gravity_simple_drop() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
@@ -1359,40 +3881,127 @@ mod tests {
     }
 
     #[test]
-    fn board_gravity_drop_above_equidistant_air_prefers_shift_left() {
+    fn board_gravity_drop_above_equidistant_air_prefers_shift_left() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(5, 15), TestPiece::First);
+
+        board.set_piece(Pos::new(4, 6), TestPiece::Second);
+        board.set_piece(Pos::new(5, 8), TestPiece::First);
+        board.set_piece(Pos::new(6, 10), TestPiece::First);
+
+        board.set_barrier_between(Pos::new(2, 6), Pos::new(3, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(3, 5), Pos::new(3, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(4, 5), Pos::new(4, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(5, 5), Pos::new(5, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(6, 5), Pos::new(6, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(7, 5), Pos::new(7, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(8, 5), Pos::new(8, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(8, 6), Pos::new(9, 6), true).unwrap();
+
+        let mut start_board = board.clone();
+        let moves = board.apply_gravity_to_board();
+
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 6)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(4, 6)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(5, 6)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(6, 6)));
+
+        for x in 0..15 {
+            for y in 0..16 {
+                if !(y == 6 && (x >= 3 || y <= 6)) {
+                    assert_eq!(TestPiece::Air, board.piece(Pos::new(x, y)));
+                }
+            }
+        }
+
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
+    }
+
+    #[test]
+    fn board_gravity_equidistant_air_right_bias_shifts_right() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        for x in 0..15 {
+            if x != 4 && x != 6 {
+                board.set_piece(Pos::new(x, 0), TestPiece::First);
+            }
+        }
+        board.set_piece(Pos::new(5, 5), TestPiece::Second);
+
+        let mut left_board = board;
+        left_board.apply_gravity_to_board_with_bias(GravityBias::Left);
+        assert_eq!(TestPiece::Air, left_board.piece(Pos::new(6, 0)));
+        assert_eq!(TestPiece::First, left_board.piece(Pos::new(4, 0)));
+
+        let mut start_board = board;
+        let moves = board.apply_gravity_to_board_with_bias(GravityBias::Right);
+
+        assert_eq!(TestPiece::Air, board.piece(Pos::new(4, 0)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(5, 0)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(6, 0)));
+        for x in 0..15 {
+            for y in 1..16 {
+                assert_eq!(TestPiece::Air, board.piece(Pos::new(x, y)));
+            }
+        }
+
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
+    }
+
+    #[test]
+    fn settle_complex_board_no_floating_pieces() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+
+        board.set_barrier_between(Pos::new(1, 5), Pos::new(1, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(0, 6), Pos::new(1, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(2, 2), Pos::new(2, 3), true).unwrap();
+        board.set_barrier_between(Pos::new(2, 8), Pos::new(2, 9), true).unwrap();
+        board.set_barrier_between(Pos::new(3, 8), Pos::new(3, 9), true).unwrap();
+        board.set_barrier_between(Pos::new(4, 8), Pos::new(4, 9), true).unwrap();
+        board.set_barrier_between(Pos::new(4, 9), Pos::new(5, 9), true).unwrap();
+        board.set_barrier_between(Pos::new(8, 4), Pos::new(8, 5), true).unwrap();
+        board.set_barrier_between(Pos::new(7, 4), Pos::new(8, 4), true).unwrap();
+
+        for x in 0..15 {
+            for y in (x % 4)..16 {
+                if (x + y) % 3 == 0 {
+                    board.set_piece(Pos::new(x, y), TestPiece::First);
+                } else if (x * y) % 5 == 1 {
+                    board.set_piece(Pos::new(x, y), TestPiece::Second);
+                }
+            }
+        }
+
+        let mut start_board = board;
+        let moves = board.settle();
+
+        assert!(!moves.is_empty());
+        assert!(board.floating_pieces().is_empty());
+        assert!(board.settle().is_empty());
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
+    }
+
+    #[test]
+    fn gravity_detailed_matches_tuple_moves() {
         let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
-        board.set_piece(Pos::new(5, 15), TestPiece::First);
-
-        board.set_piece(Pos::new(4, 6), TestPiece::Second);
-        board.set_piece(Pos::new(5, 8), TestPiece::First);
-        board.set_piece(Pos::new(6, 10), TestPiece::First);
-
-        board.set_barrier_between(Pos::new(2, 6), Pos::new(3, 6), true).unwrap();
-        board.set_barrier_between(Pos::new(3, 5), Pos::new(3, 6), true).unwrap();
-        board.set_barrier_between(Pos::new(4, 5), Pos::new(4, 6), true).unwrap();
-        board.set_barrier_between(Pos::new(5, 5), Pos::new(5, 6), true).unwrap();
-        board.set_barrier_between(Pos::new(6, 5), Pos::new(6, 6), true).unwrap();
-        board.set_barrier_between(Pos::new(7, 5), Pos::new(7, 6), true).unwrap();
-        board.set_barrier_between(Pos::new(8, 5), Pos::new(8, 6), true).unwrap();
-        board.set_barrier_between(Pos::new(8, 6), Pos::new(9, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(1, 5), Pos::new(1, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(2, 2), Pos::new(2, 3), true).unwrap();
+        board.set_piece(Pos::new(4, 15), TestPiece::First);
+        board.set_piece(Pos::new(4, 14), TestPiece::Second);
+        board.set_piece(Pos::new(3, 12), TestPiece::First);
+        board.set_piece(Pos::new(1, 13), TestPiece::Second);
+        board.set_piece(Pos::new(2, 14), TestPiece::First);
 
-        let mut start_board = board.clone();
+        let mut detailed_board = board;
         let moves = board.apply_gravity_to_board();
+        let detailed_moves = detailed_board.apply_gravity_detailed();
 
-        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 6)));
-        assert_eq!(TestPiece::First, board.piece(Pos::new(4, 6)));
-        assert_eq!(TestPiece::First, board.piece(Pos::new(5, 6)));
-        assert_eq!(TestPiece::First, board.piece(Pos::new(6, 6)));
-
-        for x in 0..15 {
-            for y in 0..16 {
-                if !(y == 6 && (x >= 3 || y <= 6)) {
-                    assert_eq!(TestPiece::Air, board.piece(Pos::new(x, y)));
-                }
-            }
+        assert!(!moves.is_empty());
+        assert_eq!(moves.len(), detailed_moves.len());
+        for ((from, to), detailed_move) in moves.into_iter().zip(detailed_moves) {
+            assert_eq!(from, detailed_move.from);
+            assert_eq!(to, detailed_move.to);
         }
-
-        assert!(moves_produce_board(&moves, &mut start_board, &board));
+        assert_eq!(board, detailed_board);
     }
 
     #[test]
@@ -1541,4 +4150,616 @@ mod tests {
 
         assert!(moves_produce_board(&moves, &mut start_board, &board));
     }
+
+    #[test]
+    fn preview_gravity_does_not_modify_board() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_barrier_between(Pos::new(1, 5), Pos::new(1, 6), true).unwrap();
+        board.set_barrier_between(Pos::new(2, 2), Pos::new(2, 3), true).unwrap();
+        board.set_piece(Pos::new(4, 15), TestPiece::First);
+        board.set_piece(Pos::new(4, 14), TestPiece::Second);
+        board.set_piece(Pos::new(3, 12), TestPiece::First);
+        board.set_piece(Pos::new(1, 13), TestPiece::Second);
+        board.set_piece(Pos::new(2, 14), TestPiece::First);
+
+        let start_board = board;
+        let preview = board.preview_gravity();
+        assert_eq!(start_board, board);
+
+        let mut gravity_board = board;
+        let moves = gravity_board.apply_gravity_to_board();
+
+        assert!(!preview.is_empty());
+        assert_eq!(moves, preview);
+        assert!(moves_produce_board(&preview, &mut board, &gravity_board));
+    }
+
+    #[test]
+    fn grid_is_column_major_view() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(3, 7), TestPiece::First);
+        board.set_piece(Pos::new(14, 0), TestPiece::Second);
+
+        let grid = board.grid();
+        assert_eq!(TestPiece::First, grid[3][7]);
+        assert_eq!(TestPiece::Second, grid[14][0]);
+        assert_eq!(TestPiece::Air, grid[7][3]);
+        assert_eq!(2, grid.iter().flatten().filter(|&&piece| piece != TestPiece::Air).count());
+    }
+
+    #[test]
+    fn top_piece_column_with_pieces() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(4, 0), TestPiece::First);
+        board.set_piece(Pos::new(4, 1), TestPiece::First);
+        board.set_piece(Pos::new(4, 2), TestPiece::Second);
+
+        assert_eq!(Some((Pos::new(4, 2), TestPiece::Second)), board.top_piece(Col::new(4)));
+        assert_eq!(Some(3), board.surface(Col::new(4)));
+    }
+
+    #[test]
+    fn top_piece_empty_column_none() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(5, 0), TestPiece::First);
+
+        assert_eq!(None, board.top_piece(Col::new(4)));
+    }
+
+    #[test]
+    fn open_surface_cells_from_surface_to_top() {
+        let mut board: BoardState<TestPiece, 3, 8> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(0, 1), TestPiece::First);
+        board.set_piece(Pos::new(0, 2), TestPiece::Second);
+        board.set_barrier_between(Pos::new(1, 5), Pos::new(1, 6), true).unwrap();
+        for y in 0..8 {
+            board.set_piece(Pos::new(2, y), TestPiece::First);
+        }
+
+        let expected: Vec<Pos<3, 8>> = (3..8).map(|y| Pos::new(0, y))
+            .chain((6..8).map(|y| Pos::new(1, y)))
+            .collect();
+        assert_eq!(expected, board.open_surface_cells());
+    }
+
+    #[test]
+    fn column_gravity_without_compact_blocked_leaves_capped_segment() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_barrier_between(Pos::new(2, 3), Pos::new(2, 4), true).unwrap();
+        board.set_piece(Pos::new(2, 2), TestPiece::First);
+        board.set_piece(Pos::new(2, 7), TestPiece::Second);
+
+        let mut compacted_board = board;
+
+        let moves = board.apply_gravity_to_column_with(Col::new(2), false);
+        assert_eq!(vec![(7, 4)], moves);
+        assert_eq!(TestPiece::First, board.piece(Pos::new(2, 2)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 4)));
+
+        let compacted_moves = compacted_board.apply_gravity_to_column_with(Col::new(2), true);
+        assert_eq!(vec![(2, 0), (7, 4)], compacted_moves);
+        assert_eq!(TestPiece::First, compacted_board.piece(Pos::new(2, 0)));
+    }
+
+    #[test]
+    fn changeset_applied_produces_other_board() {
+        let mut board_a: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board_a.set_piece(Pos::new(0, 0), TestPiece::First);
+        board_a.set_piece(Pos::new(3, 4), TestPiece::Second);
+        board_a.set_barrier_between(Pos::new(1, 1), Pos::new(1, 2), true).unwrap();
+        board_a.set_barrier_between(Pos::new(5, 5), Pos::new(6, 5), true).unwrap();
+
+        let mut board_b = board_a;
+        board_b.set_piece(Pos::new(0, 0), TestPiece::Air);
+        board_b.set_piece(Pos::new(3, 4), TestPiece::First);
+        board_b.set_piece(Pos::new(14, 15), TestPiece::Second);
+        board_b.set_barrier_between(Pos::new(1, 1), Pos::new(1, 2), false).unwrap();
+        board_b.set_barrier_between(Pos::new(8, 2), Pos::new(8, 3), true).unwrap();
+        board_b.set_barrier_between(Pos::new(13, 15), Pos::new(14, 15), true).unwrap();
+
+        let changeset = board_a.changeset(&board_b);
+        assert_eq!(3, changeset.pieces.len());
+
+        let mut applied_board = board_a;
+        applied_board.apply(&changeset);
+        assert_eq!(board_b, applied_board);
+
+        assert!(board_b.changeset(&applied_board).is_empty());
+    }
+
+    #[test]
+    fn export_import_barriers_same_barriers() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(2, 2), TestPiece::First);
+        board.set_barrier_between(Pos::new(1, 1), Pos::new(1, 2), true).unwrap();
+        board.set_barrier_between(Pos::new(5, 5), Pos::new(6, 5), true).unwrap();
+        board.set_barrier_between(Pos::new(14, 14), Pos::new(14, 15), true).unwrap();
+
+        let layout = board.export_barriers();
+
+        let mut fresh_board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        fresh_board.set_barrier_between(Pos::new(7, 7), Pos::new(7, 8), true).unwrap();
+        fresh_board.set_piece(Pos::new(3, 3), TestPiece::Second);
+        fresh_board.import_barriers(&layout);
+
+        for x in 0..15 {
+            for y in 0..16 {
+                let pos = Pos::new(x, y);
+                for dir in [Direction::North, Direction::East] {
+                    assert_eq!(board.has_barrier_toward(pos, dir), fresh_board.has_barrier_toward(pos, dir));
+                }
+            }
+        }
+
+        assert_eq!(TestPiece::Second, fresh_board.piece(Pos::new(3, 3)));
+        assert_eq!(TestPiece::Air, fresh_board.piece(Pos::new(2, 2)));
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+    enum WeightedPiece {
+        #[default]
+        Air,
+        Rock,
+        Gas,
+        Wall
+    }
+
+    impl From<()> for WeightedPiece {
+        fn from(_: ()) -> Self {
+            WeightedPiece::Rock
+        }
+    }
+
+    impl BitAnd for WeightedPiece {
+        type Output = Self;
+
+        fn bitand(self, rhs: Self) -> Self::Output {
+            match self == rhs {
+                true => self,
+                false => WeightedPiece::Air
+            }
+        }
+    }
+
+    impl Piece for WeightedPiece {
+        type MatchType = ();
+        const AIR: Self = WeightedPiece::Air;
+
+        fn weight(&self) -> i32 {
+            match self {
+                WeightedPiece::Gas => -1,
+                WeightedPiece::Wall => 0,
+                _ => 1
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_gravity_rising_and_falling_opposite_ends() {
+        let mut board: BoardState<WeightedPiece, 3, 8> = BoardState::new();
+        board.set_piece(Pos::new(1, 2), WeightedPiece::Gas);
+        board.set_piece(Pos::new(1, 5), WeightedPiece::Rock);
+
+        let mut start_board = board;
+        let moves = board.apply_gravity_to_board();
+
+        assert_eq!(WeightedPiece::Rock, board.piece(Pos::new(1, 0)));
+        assert_eq!(WeightedPiece::Gas, board.piece(Pos::new(1, 7)));
+        assert_eq!(2, board.occupied().count());
+        for (first, second) in moves {
+            start_board.swap(first, second);
+        }
+        assert_eq!(board, start_board);
+    }
+
+    #[test]
+    fn weighted_gravity_blocked_by_barriers_and_fixed_pieces() {
+        let mut board: BoardState<WeightedPiece, 3, 8> = BoardState::new();
+        board.set_barrier_between(Pos::new(0, 5), Pos::new(0, 6), true).unwrap();
+        board.set_piece(Pos::new(0, 1), WeightedPiece::Gas);
+        board.set_piece(Pos::new(0, 7), WeightedPiece::Rock);
+        board.set_piece(Pos::new(2, 3), WeightedPiece::Wall);
+        board.set_piece(Pos::new(2, 6), WeightedPiece::Rock);
+        board.set_piece(Pos::new(2, 1), WeightedPiece::Gas);
+
+        board.apply_gravity_to_board();
+
+        assert_eq!(WeightedPiece::Gas, board.piece(Pos::new(0, 5)));
+        assert_eq!(WeightedPiece::Rock, board.piece(Pos::new(0, 6)));
+        assert_eq!(WeightedPiece::Wall, board.piece(Pos::new(2, 3)));
+        assert_eq!(WeightedPiece::Rock, board.piece(Pos::new(2, 4)));
+        assert_eq!(WeightedPiece::Gas, board.piece(Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn weighted_gravity_rising_piece_with_sideways_shift_honors_bias() {
+        let mut board: BoardState<WeightedPiece, 5, 4> = BoardState::new();
+        board.set_piece(Pos::new(1, 0), WeightedPiece::Rock);
+        board.set_piece(Pos::new(2, 0), WeightedPiece::Rock);
+        board.set_piece(Pos::new(3, 0), WeightedPiece::Rock);
+        board.set_piece(Pos::new(2, 1), WeightedPiece::Gas);
+        board.set_piece(Pos::new(2, 3), WeightedPiece::Rock);
+
+        for (bias, empty_x) in [(GravityBias::Left, 4), (GravityBias::Right, 0)] {
+            let mut biased_board = board;
+            let moves = biased_board.apply_gravity_to_board_with_bias(bias);
+
+            assert_eq!(WeightedPiece::Gas, biased_board.piece(Pos::new(2, 3)));
+            assert_eq!(WeightedPiece::Air, biased_board.piece(Pos::new(empty_x, 0)));
+            assert_eq!(4, (0..5).filter(|&x| biased_board.piece(Pos::new(x, 0)) == WeightedPiece::Rock).count());
+            assert_eq!(5, biased_board.occupied().count());
+
+            let mut replayed_board = board;
+            for (first, second) in moves {
+                replayed_board.swap(first, second);
+            }
+            assert_eq!(biased_board, replayed_board);
+        }
+    }
+
+    #[test]
+    fn landing_position_ledge_uses_dropped_piece_weight() {
+        let mut board: BoardState<WeightedPiece, 3, 8> = BoardState::new();
+        board.set_barrier_between(Pos::new(1, 3), Pos::new(1, 4), true).unwrap();
+        board.set_piece(Pos::new(1, 5), WeightedPiece::Gas);
+
+        assert_eq!(Some(Pos::new(1, 4)), board.landing_position(Col::new(1), WeightedPiece::Rock));
+        assert_eq!(Some(Pos::new(1, 7)), board.landing_position(Col::new(1), WeightedPiece::Gas));
+        assert_eq!(Some(Pos::new(1, 7)), board.landing_position(Col::new(1), WeightedPiece::Wall));
+        assert_eq!(WeightedPiece::Gas, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn map_in_place_upgrades_marker_pieces() {
+        let mut board: BoardState<TestPiece, 15, 16> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(7, 9), TestPiece::First);
+        board.set_piece(Pos::new(3, 3), TestPiece::Second);
+
+        let mut visited = 0;
+        board.map_in_place(|_, piece| {
+            visited += 1;
+            match piece {
+                TestPiece::First => TestPiece::Second,
+                other => other
+            }
+        });
+
+        assert_eq!(15 * 16, visited);
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(0, 0)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(7, 9)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 3)));
+        assert_eq!(3, board.occupied().count());
+    }
+
+    #[test]
+    fn apply_gravity_to_region_settles_to_region_floor() {
+        let mut board: BoardState<TestPiece, 6, 8> = BoardState::new();
+        board.set_piece(Pos::new(2, 6), TestPiece::First);
+        board.set_piece(Pos::new(3, 4), TestPiece::Second);
+        board.set_piece(Pos::new(3, 5), TestPiece::First);
+        board.set_piece(Pos::new(0, 6), TestPiece::Second);
+        board.set_piece(Pos::new(2, 7), TestPiece::Second);
+
+        let mut start_board = board;
+        let moves = board.apply_gravity_to_region(Pos::new(1, 6), Pos::new(3, 3));
+
+        assert_eq!(TestPiece::First, board.piece(Pos::new(2, 3)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(3, 3)));
+        assert_eq!(TestPiece::First, board.piece(Pos::new(3, 4)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(0, 6)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 7)));
+        assert_eq!(5, board.occupied().count());
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
+    }
+
+    #[test]
+    fn apply_gravity_to_region_swapped_corners_same_region() {
+        let mut board: BoardState<TestPiece, 6, 8> = BoardState::new();
+        board.set_piece(Pos::new(2, 6), TestPiece::First);
+        board.set_piece(Pos::new(3, 5), TestPiece::Second);
+        board.set_piece(Pos::new(0, 6), TestPiece::Second);
+
+        let mut swapped_board = board;
+        let moves = board.apply_gravity_to_region(Pos::new(1, 6), Pos::new(3, 3));
+        let swapped_moves = swapped_board.apply_gravity_to_region(Pos::new(3, 3), Pos::new(1, 6));
+
+        assert_eq!(moves, swapped_moves);
+        assert_eq!(board, swapped_board);
+        assert_eq!(TestPiece::First, board.piece(Pos::new(2, 3)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(0, 6)));
+    }
+
+    #[test]
+    fn air_mask_bits_match_air_cells() {
+        let mut board: BoardState<TestPiece, 9, 8> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(4, 3), TestPiece::Second);
+        board.set_piece(Pos::new(8, 7), TestPiece::First);
+
+        let mask = board.air_mask();
+
+        assert_eq!(9 * 8 - 3, mask.count());
+        for x in 0..9 {
+            for y in 0..8 {
+                let pos = Pos::new(x, y);
+                assert_eq!(board.piece(pos) == TestPiece::Air, mask.get(pos));
+            }
+        }
+    }
+
+    #[test]
+    fn type_mask_bits_match_pieces_with_type() {
+        let mut board: BoardState<TestPiece, 9, 8> = BoardState::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(4, 3), TestPiece::Second);
+        board.set_piece(Pos::new(8, 7), TestPiece::First);
+        board.set_piece(Pos::new(2, 6), TestPiece::First);
+
+        let mask = board.type_mask(0);
+        let matching_count = board.occupied()
+            .filter(|&(_, piece)| (piece & TestPiece::from(0)) != TestPiece::Air)
+            .count();
+
+        assert_eq!(matching_count, mask.count());
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(2, 6), Pos::new(8, 7)], mask.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn find_runs_horizontal_run_respects_edges() {
+        let mut board: BoardState<TestPiece, 9, 8> = BoardState::new();
+        board.set_piece(Pos::new(6, 0), TestPiece::First);
+        board.set_piece(Pos::new(7, 0), TestPiece::First);
+        board.set_piece(Pos::new(8, 0), TestPiece::First);
+        board.set_piece(Pos::new(0, 1), TestPiece::First);
+        board.set_piece(Pos::new(1, 1), TestPiece::First);
+        board.set_piece(Pos::new(2, 2), TestPiece::First);
+        board.set_piece(Pos::new(2, 3), TestPiece::Second);
+        board.set_piece(Pos::new(2, 4), TestPiece::First);
+
+        assert_eq!(vec![Pos::new(6, 0)], board.find_runs(0, 3));
+        assert!(board.find_runs(0, 4).is_empty());
+        assert_eq!(vec![Pos::new(6, 0), Pos::new(7, 0), Pos::new(0, 1)], board.find_runs(0, 2));
+    }
+
+    #[test]
+    fn find_runs_vertical_run_at_top_edge() {
+        let mut board: BoardState<TestPiece, 9, 8> = BoardState::new();
+        board.set_piece(Pos::new(3, 5), TestPiece::Second);
+        board.set_piece(Pos::new(3, 6), TestPiece::Second);
+        board.set_piece(Pos::new(3, 7), TestPiece::Second);
+        board.set_piece(Pos::new(4, 0), TestPiece::Second);
+
+        assert_eq!(vec![Pos::new(3, 5)], board.find_runs(1, 3));
+        assert!(board.find_runs(0, 3).is_empty());
+    }
+
+    #[test]
+    fn fill_ratio_empty_partial_and_full() {
+        let mut board: BoardState<TestPiece, 4, 5> = BoardState::new();
+        assert_eq!(0.0, board.fill_ratio());
+
+        board.set_piece(Pos::new(1, 2), TestPiece::First);
+        board.set_piece(Pos::new(3, 4), TestPiece::Second);
+        assert_eq!(0.1, board.fill_ratio());
+
+        for x in 0..4 {
+            for y in 0..5 {
+                board.set_piece(Pos::new(x, y), TestPiece::First);
+            }
+        }
+        assert_eq!(1.0, board.fill_ratio());
+    }
+
+    #[test]
+    fn shift_north_pushes_top_row_off_and_fills_bottom() {
+        let mut board: BoardState<TestPiece, 3, 3> = BoardState::from_rows([
+            [TestPiece::First, TestPiece::Air, TestPiece::Air],
+            [TestPiece::Air, TestPiece::Second, TestPiece::Air],
+            [TestPiece::Second, TestPiece::Second, TestPiece::First]
+        ]);
+        board.set_barrier_between(Pos::new(0, 0), Pos::new(0, 1), true).unwrap();
+
+        board.shift(Direction::North, 1, TestPiece::First);
+
+        let expected: BoardState<TestPiece, 3, 3> = BoardState::from_rows([
+            [TestPiece::First, TestPiece::First, TestPiece::First],
+            [TestPiece::First, TestPiece::Air, TestPiece::Air],
+            [TestPiece::Air, TestPiece::Second, TestPiece::Air]
+        ]);
+        assert_eq!(expected.grid(), board.grid());
+        assert!(board.has_barrier_between(Pos::new(0, 0), Pos::new(0, 1)));
+    }
+
+    #[test]
+    fn shift_west_past_width_fills_everything() {
+        let mut board: BoardState<TestPiece, 3, 3> = BoardState::new();
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+
+        board.shift(Direction::West, 2, TestPiece::Air);
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(0, 1)));
+
+        board.shift(Direction::West, 5, TestPiece::First);
+        assert_eq!(9, board.occupied().filter(|&(_, piece)| piece == TestPiece::First).count());
+    }
+
+    #[test]
+    fn is_symmetric_symmetric_and_asymmetric_boards() {
+        let symmetric: BoardState<TestPiece, 3, 3> = BoardState::from_rows([
+            [TestPiece::First, TestPiece::Second, TestPiece::First],
+            [TestPiece::Air, TestPiece::Second, TestPiece::Air],
+            [TestPiece::First, TestPiece::Second, TestPiece::First]
+        ]);
+        assert!(symmetric.is_symmetric(Axis::Horizontal));
+        assert!(symmetric.is_symmetric(Axis::Vertical));
+
+        let asymmetric: BoardState<TestPiece, 3, 3> = BoardState::from_rows([
+            [TestPiece::First, TestPiece::Second, TestPiece::Second],
+            [TestPiece::Air, TestPiece::Second, TestPiece::Air],
+            [TestPiece::First, TestPiece::Second, TestPiece::Second]
+        ]);
+        assert!(asymmetric.is_symmetric(Axis::Horizontal));
+        assert!(!asymmetric.is_symmetric(Axis::Vertical));
+    }
+
+    #[test]
+    fn adjacent_pairs_one_horizontal_pair_once() {
+        let mut board: BoardState<TestPiece, 5, 5> = BoardState::new();
+        board.set_piece(Pos::new(1, 2), TestPiece::First);
+        board.set_piece(Pos::new(2, 2), TestPiece::First);
+        board.set_piece(Pos::new(3, 3), TestPiece::First);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+
+        let pairs: Vec<_> = board.adjacent_pairs(0).collect();
+        assert_eq!(vec![(Pos::new(1, 2), Pos::new(2, 2))], pairs);
+    }
+
+    #[test]
+    fn adjacent_pairs_barrier_splits_pair() {
+        let mut board: BoardState<TestPiece, 5, 5> = BoardState::new();
+        board.set_piece(Pos::new(1, 2), TestPiece::First);
+        board.set_piece(Pos::new(1, 3), TestPiece::First);
+        board.set_barrier_between(Pos::new(1, 2), Pos::new(1, 3), true).unwrap();
+
+        assert_eq!(0, board.adjacent_pairs(0).count());
+    }
+
+    #[test]
+    fn apply_gravity_to_board_limited_falls_at_most_max() {
+        let mut board: BoardState<TestPiece, 3, 12> = BoardState::new();
+        board.set_piece(Pos::new(1, 10), TestPiece::First);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+
+        let mut start_board = board;
+        let moves = board.apply_gravity_to_board_limited(3);
+
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 7)));
+        assert_eq!(TestPiece::Second, board.piece(Pos::new(2, 0)));
+        assert_eq!(2, board.occupied().count());
+        assert!(moves_produce_board(&moves, &mut start_board, &board));
+
+        while !board.apply_gravity_to_board_limited(3).is_empty() {}
+        assert_eq!(TestPiece::First, board.piece(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn corners_distinct_corner_pieces() {
+        let board: BoardState<TestPiece, 4, 3> = BoardState::from_rows([
+            [TestPiece::First, TestPiece::Air, TestPiece::Air, TestPiece::Second],
+            [TestPiece::Second, TestPiece::First, TestPiece::First, TestPiece::Second],
+            [TestPiece::Air, TestPiece::Second, TestPiece::Second, TestPiece::First]
+        ]);
+
+        assert_eq!(Some([
+            (Pos::new(0, 0), TestPiece::First),
+            (Pos::new(3, 0), TestPiece::Second),
+            (Pos::new(0, 2), TestPiece::Air),
+            (Pos::new(3, 2), TestPiece::First)
+        ]), board.corners());
+    }
+
+    fn zero_size_board_results_empty<const W: usize, const H: usize>() {
+        let mut board: BoardState<TestPiece, W, H> = BoardState::new();
+
+        assert!(board.barriers_valid());
+        assert_eq!(0, board.occupied().count());
+        assert_eq!(0.0, board.fill_ratio());
+        assert!(board.corners().is_none());
+        assert!(board.air_mask().is_empty());
+        assert!(board.type_mask(0).is_empty());
+        assert!(board.air_mask().shift(Direction::West).iter().next().is_none());
+        assert!(board.find_runs(0, 3).is_empty());
+        assert!(board.present_types().is_empty());
+        assert_eq!(0, board.adjacent_pairs(0).count());
+        assert!(board.floating_pieces().is_empty());
+        assert!(board.open_surface_cells().is_empty());
+        assert!(board.is_symmetric(Axis::Horizontal));
+        assert!(board.is_symmetric(Axis::Vertical));
+        assert!(board.preview_gravity().is_empty());
+        assert!(board.changeset(&BoardState::new()).is_empty());
+
+        assert!(board.apply_gravity_to_board().is_empty());
+        assert!(board.apply_gravity_to_board_with_bias(GravityBias::Right).is_empty());
+        assert!(board.apply_gravity_detailed().is_empty());
+        assert!(board.settle().is_empty());
+        assert!(board.apply_gravity_step().is_empty());
+        assert!(board.apply_gravity_to_board_limited(3).is_empty());
+        assert!(board.apply_diagonal_gravity().is_empty());
+        assert_eq!(MergeGravityResult::default(), board.apply_merge_gravity());
+
+        for dir in [Direction::North, Direction::South, Direction::East, Direction::West] {
+            assert!(board.refill_from(dir, |_| TestPiece::First).is_empty());
+            board.shift(dir, 1, TestPiece::First);
+        }
+
+        board.flip_horizontal();
+        board.flip_vertical();
+        board.map_in_place(|_, _| TestPiece::First);
+        board.import_barriers(&board.export_barriers());
+
+        assert_eq!(board, BoardState::from_sparse(&board.to_sparse()));
+        assert_eq!(0, board.occupied().count());
+    }
+
+    #[test]
+    fn zero_size_board_zero_by_zero_results_empty() {
+        zero_size_board_results_empty::<0, 0>();
+    }
+
+    #[test]
+    fn zero_size_board_zero_width_results_empty() {
+        zero_size_board_results_empty::<0, 5>();
+    }
+
+    #[test]
+    fn zero_size_board_zero_height_results_empty() {
+        zero_size_board_results_empty::<5, 0>();
+    }
+
+    #[test]
+    fn thin_board_gravity_settles() {
+        let mut tall: BoardState<TestPiece, 1, 5> = BoardState::new();
+        tall.set_piece(Pos::new(0, 4), TestPiece::First);
+        tall.apply_gravity_to_board();
+        tall.apply_gravity_to_column(Col::new(0));
+        assert_eq!(TestPiece::First, tall.piece(Pos::new(0, 0)));
+
+        let mut wide: BoardState<TestPiece, 5, 1> = BoardState::new();
+        wide.set_piece(Pos::new(3, 0), TestPiece::First);
+        assert!(wide.apply_gravity_to_board().is_empty());
+        assert!(wide.apply_gravity_to_column(Col::new(3)).is_empty());
+        assert_eq!(TestPiece::First, wide.piece(Pos::new(3, 0)));
+    }
+
+    #[test]
+    fn nearest_air_surrounded_cell_finds_reachable_hole() {
+        let mut board: BoardState<TestPiece, 5, 5> = BoardState::new();
+        for x in 0..5 {
+            for y in 0..5 {
+                board.set_piece(Pos::new(x, y), TestPiece::First);
+            }
+        }
+        board.set_piece(Pos::new(2, 4), TestPiece::Air);
+        board.set_piece(Pos::new(2, 1), TestPiece::Air);
+        board.set_barrier_between(Pos::new(2, 3), Pos::new(2, 4), true).unwrap();
+
+        assert_eq!(Some(Pos::new(2, 1)), board.nearest_air(Pos::new(2, 3)));
+        assert_eq!(Some(Pos::new(2, 4)), board.nearest_air(Pos::new(1, 4)));
+        assert_eq!(Some(Pos::new(2, 1)), board.nearest_air(Pos::new(2, 1)));
+    }
+
+    #[test]
+    fn nearest_air_fully_walled_cell_none() {
+        let mut board: BoardState<TestPiece, 5, 5> = BoardState::new();
+        let walled = Pos::new(2, 2);
+        board.set_piece(walled, TestPiece::Second);
+
+        for dir in [Direction::North, Direction::South, Direction::East, Direction::West] {
+            board.set_barrier_between(walled, walled.adjacent(dir).unwrap(), true).unwrap();
+        }
+
+        assert_eq!(None, board.nearest_air(walled));
+        assert_eq!(Some(Pos::new(2, 3)), board.nearest_air(Pos::new(2, 3)));
+    }
 }
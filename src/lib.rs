@@ -1,9 +1,13 @@
+mod bitboard;
 mod board;
 mod matching;
 mod position;
 mod match_board;
+mod limited_board;
 
 pub use crate::position::*;
 pub use crate::matching::*;
+pub use crate::bitboard::*;
 pub use crate::board::*;
 pub use crate::match_board::*;
+pub use crate::limited_board::*;
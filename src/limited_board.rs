@@ -0,0 +1,208 @@
+use crate::{InvalidSwap, MatchBoard, Piece, Pos};
+
+/// Errors possible when a player swaps pieces on a [`LimitedMatchBoard`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SwapError<const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+
+    /// The player has already made every move they are allowed
+    NoMovesRemaining,
+
+    /// The swap was rejected by [`MatchBoard::try_swap`]
+    Invalid(InvalidSwap<BOARD_WIDTH, BOARD_HEIGHT>)
+
+}
+
+/// Wraps a [`MatchBoard`] to limit how many swaps the player can make, which is a common
+/// rule in puzzle games. Only swaps made through this wrapper count as moves. Once every
+/// move has been used, further swaps are rejected without changing the board.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LimitedMatchBoard<
+    'a,
+    M,
+    P,
+    const BOARD_WIDTH: usize,
+    const BOARD_HEIGHT: usize
+> {
+    board: MatchBoard<'a, M, P, BOARD_WIDTH, BOARD_HEIGHT>,
+    move_limit: usize,
+    moves_made: usize
+}
+
+impl<'a, M: Copy, P: Piece<MatchType=M>, const W: usize, const H: usize> LimitedMatchBoard<'a, M, P, W, H> {
+
+    /// Creates a new move-limited board.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board to play on
+    /// * `move_limit` - the total number of swaps the player can make
+    pub fn new(board: MatchBoard<'a, M, P, W, H>, move_limit: usize) -> LimitedMatchBoard<'a, M, P, W, H> {
+        LimitedMatchBoard { board, move_limit, moves_made: 0 }
+    }
+
+    /// Gets the number of swaps the player can still make.
+    pub fn moves_remaining(&self) -> usize {
+        self.move_limit - self.moves_made
+    }
+
+    /// Gets the number of swaps the player has made so far.
+    pub fn moves_made(&self) -> usize {
+        self.moves_made
+    }
+
+    /// Swaps two pieces and uses up one move, like [`MatchBoard::swap`].
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SwapError::NoMovesRemaining`] if every move has been used.
+    pub fn swap(&mut self, first: Pos<W, H>, second: Pos<W, H>) -> Result<(), SwapError<W, H>> {
+        self.use_move()?;
+        self.board.swap(first, second);
+        Ok(())
+    }
+
+    /// Swaps two pieces if the swap is valid, like [`MatchBoard::try_swap`]. Only a valid swap
+    /// uses up a move.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SwapError::NoMovesRemaining`] if every move has been used, or
+    /// [`SwapError::Invalid`] if the swap is not valid.
+    pub fn try_swap(&mut self, first: Pos<W, H>, second: Pos<W, H>) -> Result<(), SwapError<W, H>> {
+        if self.moves_remaining() == 0 {
+            return Err(SwapError::NoMovesRemaining);
+        }
+
+        self.board.try_swap(first, second).map_err(SwapError::Invalid)?;
+        self.use_move()
+    }
+
+    /// Gets the wrapped board.
+    pub fn board(&self) -> &MatchBoard<'a, M, P, W, H> {
+        &self.board
+    }
+
+    /// Gets the wrapped board for changes that are not player moves, such as clearing matches
+    /// or refilling the board. Swaps made directly on the wrapped board do not use up moves.
+    pub fn board_mut(&mut self) -> &mut MatchBoard<'a, M, P, W, H> {
+        &mut self.board
+    }
+
+    /// Ends the limit and returns the wrapped board.
+    pub fn into_inner(self) -> MatchBoard<'a, M, P, W, H> {
+        self.board
+    }
+
+    /// Uses up one move.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SwapError::NoMovesRemaining`] if every move has already been used.
+    fn use_move(&mut self) -> Result<(), SwapError<W, H>> {
+        if self.moves_remaining() == 0 {
+            return Err(SwapError::NoMovesRemaining);
+        }
+
+        self.moves_made += 1;
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::BitAnd;
+    use crate::{BoardState, InvalidSwap, LimitedMatchBoard, MatchBoard, MatchPattern, Piece, Pos, SwapError};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+    enum TestMatchType {
+        First
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+    enum TestPiece {
+        #[default]
+        None,
+        First,
+        Second
+    }
+
+    impl From<TestMatchType> for TestPiece {
+        fn from(value: TestMatchType) -> Self {
+            match value {
+                TestMatchType::First => TestPiece::First
+            }
+        }
+    }
+
+    impl BitAnd for TestPiece {
+        type Output = Self;
+
+        fn bitand(self, rhs: Self) -> Self::Output {
+            match self == rhs {
+                true => self,
+                false => TestPiece::None
+            }
+        }
+    }
+
+    impl Piece for TestPiece {
+        type MatchType = TestMatchType;
+        const AIR: Self = Self::None;
+    }
+
+    #[test]
+    fn swap_past_limit_rejected() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
+        let mut limited_board = LimitedMatchBoard::new(MatchBoard::new(board, vec![&pattern]), 2);
+        assert_eq!(2, limited_board.moves_remaining());
+
+        assert_eq!(Ok(()), limited_board.swap(Pos::new(0, 0), Pos::new(1, 0)));
+        assert_eq!(Ok(()), limited_board.swap(Pos::new(0, 0), Pos::new(1, 0)));
+        assert_eq!(0, limited_board.moves_remaining());
+
+        let before_rejected = limited_board.clone();
+        assert_eq!(Err(SwapError::NoMovesRemaining), limited_board.swap(Pos::new(0, 0), Pos::new(1, 0)));
+        assert_eq!(before_rejected, limited_board);
+        assert_eq!(2, limited_board.moves_made());
+    }
+
+    #[test]
+    fn try_swap_invalid_does_not_use_move() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 1), TestPiece::First);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
+        let mut limited_board = LimitedMatchBoard::new(MatchBoard::new(board, vec![&pattern]), 1);
+
+        assert_eq!(
+            Err(SwapError::Invalid(InvalidSwap { first: Pos::new(0, 1), second: Pos::new(0, 2) })),
+            limited_board.try_swap(Pos::new(0, 1), Pos::new(0, 2))
+        );
+        assert_eq!(1, limited_board.moves_remaining());
+
+        assert_eq!(Ok(()), limited_board.try_swap(Pos::new(2, 1), Pos::new(2, 0)));
+        assert_eq!(1, limited_board.board().matches().len());
+        assert_eq!(Err(SwapError::NoMovesRemaining), limited_board.try_swap(Pos::new(2, 1), Pos::new(2, 0)));
+    }
+}
@@ -1,5 +1,104 @@
-use std::collections::HashSet;
-use crate::{BoardState, Match, MatchMove, MatchPattern, Piece, Pos};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
+use crate::{BitBoard, BoardState, Direction, GravityBias, Match, MatchMove, MatchPattern, Orientation, OwnedMatch, Piece, Pos, RunPattern};
+
+/// An operation that changed a [`MatchBoard`], recorded when logging is enabled.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoardEvent<P, const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+
+    /// A piece was placed at a position
+    SetPiece(Pos<BOARD_WIDTH, BOARD_HEIGHT>, P),
+
+    /// The pieces at two positions were swapped
+    Swap(Pos<BOARD_WIDTH, BOARD_HEIGHT>, Pos<BOARD_WIDTH, BOARD_HEIGHT>),
+
+    /// All matches were removed, clearing the given positions (in sorted order)
+    RemoveMatches(Vec<Pos<BOARD_WIDTH, BOARD_HEIGHT>>)
+
+}
+
+/// A swap rejected by [`MatchBoard::try_swap`], with the positions that were to be swapped so
+/// that the attempt can still be shown to the player.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct InvalidSwap<const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+
+    /// The first position of the rejected swap
+    pub first: Pos<BOARD_WIDTH, BOARD_HEIGHT>,
+
+    /// The second position of the rejected swap
+    pub second: Pos<BOARD_WIDTH, BOARD_HEIGHT>
+
+}
+
+/// The geometric shape of the positions in a match. Games often award special pieces for
+/// matches shaped like an L, T, or cross, where two lines meet.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchShape {
+
+    /// All positions share a row or a column
+    Line,
+
+    /// A row and a column that meet at an end of both
+    L,
+
+    /// A row and a column where the end of one meets the middle of the other
+    T,
+
+    /// A row and a column that cross in the middle of both
+    Cross,
+
+    /// A filled square at least two positions wide
+    Square,
+
+    /// Any shape not listed above
+    Other
+
+}
+
+impl MatchShape {
+
+    /// Checks whether this shape is made of a row and a column that meet, which includes the
+    /// L, T, and cross shapes.
+    pub fn is_intersection(self) -> bool {
+        matches!(self, MatchShape::L | MatchShape::T | MatchShape::Cross)
+    }
+
+}
+
+/// A current match classified by its shape. See [`MatchBoard::special_matches`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecialMatch<const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+
+    /// The shape of the match
+    pub shape: MatchShape,
+
+    /// Where the row and column meet for intersection shapes, or the center of the match
+    /// (see [`Match::center`]) for all other shapes
+    pub pivot: Pos<BOARD_WIDTH, BOARD_HEIGHT>,
+
+    /// The positions in the match, in sorted order
+    pub positions: Vec<Pos<BOARD_WIDTH, BOARD_HEIGHT>>
+
+}
+
+/// The changes made by one round of [`MatchBoard::step`], in the order they were made.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct StepResult<P, const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+
+    /// The positions of the matched pieces that were replaced with air
+    pub cleared: HashSet<Pos<BOARD_WIDTH, BOARD_HEIGHT>>,
+
+    /// The swaps made by gravity, in the order they were applied
+    pub moves: Vec<(Pos<BOARD_WIDTH, BOARD_HEIGHT>, Pos<BOARD_WIDTH, BOARD_HEIGHT>)>,
+
+    /// The new pieces that refilled the board, in the order they were placed
+    pub spawned: Vec<(Pos<BOARD_WIDTH, BOARD_HEIGHT>, P)>
+
+}
 
 /// Keeps track of the current board state and computes matches.
 ///
@@ -22,11 +121,18 @@ pub struct MatchBoard<
 > {
     board: BoardState<P, BOARD_WIDTH, BOARD_HEIGHT>,
     patterns: Vec<&'a MatchPattern<M, BOARD_WIDTH, BOARD_HEIGHT>>,
+    run_patterns: Vec<&'a RunPattern<M, BOARD_WIDTH, BOARD_HEIGHT>>,
     matches: Vec<Match<'a, M, BOARD_WIDTH, BOARD_HEIGHT>>,
-    match_moves: Vec<MatchMove<'a, M, BOARD_WIDTH, BOARD_HEIGHT>>
+    last_matches: Vec<Match<'a, M, BOARD_WIDTH, BOARD_HEIGHT>>,
+    match_moves: Vec<Option<MatchMove<'a, M, BOARD_WIDTH, BOARD_HEIGHT>>>,
+    inert: BitBoard<BOARD_WIDTH, BOARD_HEIGHT>,
+    enabled_patterns: Vec<bool>,
+    allow_diagonal_swaps: bool,
+    require_both_occupied: bool,
+    log: Option<Vec<BoardEvent<P, BOARD_WIDTH, BOARD_HEIGHT>>>
 }
 
-impl<M: Copy, P: Piece<MatchType=M>, const W: usize, const H: usize> MatchBoard<'_, M, P, W, H> {
+impl<'a, M: Copy, P: Piece<MatchType=M>, const W: usize, const H: usize> MatchBoard<'a, M, P, W, H> {
 
     /// Creates a new match board.
     ///
@@ -38,12 +144,77 @@ impl<M: Copy, P: Piece<MatchType=M>, const W: usize, const H: usize> MatchBoard<
     ///                order provided. For example, if one pattern matches a column of five pieces
     ///                and another matches a column of three pieces, the column of five pattern
     ///                should probably be first.
-    pub fn new(board: BoardState<P, W, H>, patterns: Vec<&MatchPattern<M, W, H>>) -> MatchBoard<M, P, W, H> {
+    #[allow(clippy::doc_overindented_list_items)]
+    pub fn new(board: BoardState<P, W, H>, patterns: Vec<&MatchPattern<M, W, H>>) -> MatchBoard<'_, M, P, W, H> {
+        MatchBoard::with_log(board, patterns, false)
+    }
+
+    /// Creates a new match board that optionally records every change made to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - initial board state of the game (or the previous state if the game is
+    ///             being resumed after a game shutdown)
+    /// * `patterns` - match patterns to use to check for matches, in the same order as
+    ///                [`MatchBoard::new`]
+    /// * `log` - whether to record changes to the board. See [`MatchBoard::take_log`].
+    #[allow(clippy::doc_overindented_list_items)]
+    pub fn with_log(board: BoardState<P, W, H>, patterns: Vec<&MatchPattern<M, W, H>>, log: bool) -> MatchBoard<'_, M, P, W, H> {
+        MatchBoard::with_options(board, patterns, Vec::new(), log, false)
+    }
+
+    /// Creates a new match board that also finds lines of variable length.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - initial board state of the game (or the previous state if the game is
+    ///             being resumed after a game shutdown)
+    /// * `patterns` - match patterns to use to check for matches, in the same order as
+    ///                [`MatchBoard::new`]
+    /// * `run_patterns` - patterns for lines of pieces, which are checked in addition to
+    ///                    the fixed patterns. Each line is found as a single match of its
+    ///                    full length. Run patterns are not used to find match moves.
+    #[allow(clippy::doc_overindented_list_items)]
+    pub fn with_run_patterns<'b>(board: BoardState<P, W, H>, patterns: Vec<&'b MatchPattern<M, W, H>>,
+                                 run_patterns: Vec<&'b RunPattern<M, W, H>>) -> MatchBoard<'b, M, P, W, H> {
+        MatchBoard::with_options(board, patterns, run_patterns, false, false)
+    }
+
+    /// Creates a new match board with every construction option.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - initial board state of the game (or the previous state if the game is
+    ///             being resumed after a game shutdown)
+    /// * `patterns` - match patterns to use to check for matches, in the same order as
+    ///                [`MatchBoard::new`]
+    /// * `run_patterns` - patterns for lines of pieces, as in [`MatchBoard::with_run_patterns`]
+    /// * `log` - whether to record changes to the board. See [`MatchBoard::take_log`].
+    /// * `allow_diagonal_swaps` - whether pieces may also be swapped with their diagonal
+    ///                            neighbors. This affects which match moves are found and
+    ///                            which swaps [`MatchBoard::try_swap`] accepts. Barriers
+    ///                            never block a diagonal swap.
+    #[allow(clippy::doc_overindented_list_items)]
+    pub fn with_options<'b>(board: BoardState<P, W, H>, patterns: Vec<&'b MatchPattern<M, W, H>>,
+                            run_patterns: Vec<&'b RunPattern<M, W, H>>, log: bool,
+                            allow_diagonal_swaps: bool) -> MatchBoard<'b, M, P, W, H> {
+        let enabled_patterns = vec![true; patterns.len()];
+
         let mut match_board = MatchBoard {
             board,
             patterns,
+            run_patterns,
             matches: Vec::new(),
-            match_moves: Vec::new()
+            last_matches: Vec::new(),
+            match_moves: (0..(W * H)).map(|_| None).collect(),
+            inert: BitBoard::new(),
+            enabled_patterns,
+            allow_diagonal_swaps,
+            require_both_occupied: false,
+            log: match log {
+                true => Some(Vec::new()),
+                false => None
+            }
         };
 
         match_board.add_initial_matches();
@@ -75,9 +246,33 @@ impl<M: Copy, P: Piece<MatchType=M>, const W: usize, const H: usize> MatchBoard<
     pub fn set_piece(&mut self, pos: Pos<W, H>, piece: P) -> P {
         let old_piece = self.board.set_piece(pos, piece);
         self.recompute_matches(pos);
+        self.record(BoardEvent::SetPiece(pos, piece));
         old_piece
     }
 
+    /// Replaces every piece on the board with the result of a function, like
+    /// [`BoardState::map_in_place`]. Matches are updated once for all the changed positions
+    /// afterward, and each changed piece is logged as a [`BoardEvent::SetPiece`].
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - receives each position and its current piece and returns the new piece
+    pub fn map_in_place<F: FnMut(Pos<W, H>, P) -> P>(&mut self, f: F) {
+        let prev_board = self.board;
+        self.board.map_in_place(f);
+
+        let changed_positions: Vec<Pos<W, H>> = (0..W)
+            .flat_map(|x| (0..H).map(move |y| Pos::new(x, y)))
+            .filter(|&pos| prev_board.piece(pos) != self.board.piece(pos))
+            .collect();
+
+        for &pos in changed_positions.iter() {
+            self.record(BoardEvent::SetPiece(pos, self.board.piece(pos)));
+        }
+
+        self.recompute_matches_at(&changed_positions);
+    }
+
     /// Swap two pieces on the board. The order of two positions provided does not matter.
     /// The space is marked as needing a match check.
     ///
@@ -92,271 +287,1124 @@ impl<M: Copy, P: Piece<MatchType=M>, const W: usize, const H: usize> MatchBoard<
 
         self.board.swap(first, second);
 
-        self.recompute_matches(first);
-        self.recompute_matches(second);
+        self.recompute_matches_at(&[first, second]);
+        self.record(BoardEvent::Swap(first, second));
     }
 
-    /// Gets all matches on the board. Matches are always based on the current board
-    /// state.
-    pub fn matches(&self) -> &[Match<M, W, H>] {
-        &self.matches[..]
+    /// Finds the matches that would be created by placing a piece at the given position,
+    /// without changing the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position where the piece would be placed
+    /// * `piece` - the piece to place
+    pub fn preview_set(&self, pos: Pos<W, H>, piece: P) -> Vec<OwnedMatch<M, W, H>> {
+        self.board_with_piece(pos, piece).matches.iter()
+            .filter(|new_match| new_match.contains(pos))
+            .map(OwnedMatch::from)
+            .collect()
     }
 
-    /// Gets all available moves on the board that create a match, where one piece needs to
-    /// change to create a match.
-    pub fn match_moves(&self) -> Vec<MatchMove<M, W, H>> {
-        let mut match_moves = Vec::new();
-
-        for x in 0..W {
-            for y in 0..H {
-                let possible_match_move = self.patterns.iter().find_map(|pattern| {
-                    self.check_close_pattern(
-                        pattern,
-                        Pos::new(x, y)
-                    )
-                });
-
-                if let Some(match_move) = possible_match_move {
-                    let is_new_match = match_move.iter().all(|pos| MatchBoard::<M, P, W, H>::is_pos_unchecked(pos, x, y))
-                        && MatchBoard::<M, P, W, H>::is_pos_unchecked(&match_move.missing_pos(), x, y);
-                    if is_new_match {
-                        match_moves.push(match_move);
-                    }
-                }
-            }
-        };
+    /// Checks whether setting a piece would do anything useful, without changing the board. A
+    /// placement is useful if the new piece would be part of a match, like those returned by
+    /// [`MatchBoard::preview_set`], or part of a match move, either as one of the move's pieces
+    /// or as the piece that completes it. Useful for pruning placements when searching.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to set
+    /// * `piece` - the piece to put at the given position
+    pub fn placement_useful(&self, pos: Pos<W, H>, piece: P) -> bool {
+        let preview_board = self.board_with_piece(pos, piece);
 
-        match_moves
+        preview_board.matches.iter().any(|new_match| new_match.contains(pos))
+            || preview_board.match_moves.iter()
+                .flatten()
+                .any(|match_move| match_move.contains(pos) || match_move.completing_pos() == pos)
     }
 
-    /// Scans the initial state of the board for matches and close matches.
-    fn add_initial_matches(&mut self) {
-        for x in 0..W {
-            for y in 0..H {
-                self.add_matches_after(x, y, Pos::new(x, y));
-            }
-        };
+    /// Creates a copy of this board without a log and with a piece set at the given position.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to set
+    /// * `piece` - the piece to put at the given position
+    fn board_with_piece(&self, pos: Pos<W, H>, piece: P) -> Self {
+        let mut preview_board = self.clone();
+        preview_board.log = None;
+        preview_board.set_piece(pos, piece);
+        preview_board
     }
 
-    /// Recomputes the current set of matches when a position on the board is changed.
+    /// Finds how close a pattern is to matching at a position on the board. Checks every
+    /// variant of the pattern that includes the position and fits on the board, and returns
+    /// the most pattern positions satisfied by any one variant. Inert positions are never
+    /// satisfied. Returns zero if no variant fits on the board.
     ///
     /// # Arguments
     ///
-    /// * `changed_pos` - the position on the board that changed
-    fn recompute_matches(&mut self, changed_pos: Pos<W, H>) {
+    /// * `pattern` - the match pattern to check
+    /// * `pos` - the position that must be included in the pattern
+    pub fn best_partial(&self, pattern: &MatchPattern<M, W, H>, pos: Pos<W, H>) -> usize {
+        pattern.iter()
+            .filter_map(|&original| (pos - original).ok())
+            .filter_map(|origin| pattern.at_origin(origin))
+            .map(|grid_pos| grid_pos.into_iter()
+                .filter(|&grid_pos| !self.is_inert(grid_pos)
+                    && MatchBoard::<M, P, W, H>::piece_matches(pattern.match_type(), self.board.piece(grid_pos)))
+                .count())
+            .max()
+            .unwrap_or(0)
+    }
 
-        // TODO: replace with drain_filter() once it is stable
-        self.matches = self.matches.clone().into_iter()
-            .filter(|prev_match| !prev_match.contains(changed_pos))
-            .collect();
+    /// Counts the origins on this board where every position of a pattern fits on the board,
+    /// as checked by [`MatchPattern::at_origin`]. Patterns that span most of the board have few
+    /// origins, while a pattern with a single position fits at every position on the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the match pattern to place
+    pub fn placeable_anchors(&self, pattern: &MatchPattern<M, W, H>) -> usize {
+        (0..W).flat_map(|x| (0..H).map(move |y| Pos::new(x, y)))
+            .filter(|&origin| pattern.at_origin(origin).is_some())
+            .count()
+    }
 
-        self.add_matches_after(0, 0, changed_pos);
+    /// Counts the distinct matches that would include either position if the two pieces were
+    /// swapped, without changing the board. Useful for scoring swaps that create several
+    /// matches at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    pub fn preview_swap_match_count(&self, first: Pos<W, H>, second: Pos<W, H>) -> usize {
+        let mut preview_board = self.clone();
+        preview_board.log = None;
+        preview_board.swap(first, second);
+
+        preview_board.matches.iter()
+            .filter(|new_match| new_match.contains(first) || new_match.contains(second))
+            .count()
     }
 
-    /// Adds matches for the given changed position that have not already been found, assuming
-    /// positions before `(start_x, start_y)` have already been checked.
+    /// Swaps two pieces, runs the given function on the swapped board, and then reverts the
+    /// swap, even if the function panics. Returns the function's result. The board, its matches,
+    /// and its log are left exactly as they were before.
     ///
     /// # Arguments
     ///
-    /// * `start_x` - x-coordinate of the position currently being checked
-    /// * `start_y` - y-coordinate of the position currently being checked
-    /// * `changed_pos` - position on the board that was changed
-    fn add_matches_after(&mut self, start_x: usize, start_y: usize, changed_pos: Pos<W, H>) {
-        let possible_new_matches = self.patterns.iter().find_map(
-            |&pattern| {
-                let new_matches = self.check_pattern(pattern, changed_pos);
-                match new_matches.is_empty() {
-                    true => None,
-                    false => Some(new_matches)
-                }
-            }
-        );
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    /// * `f` - function that observes the board after the swap
+    pub fn with_swap<T>(&mut self, first: Pos<W, H>, second: Pos<W, H>, f: impl FnOnce(&Self) -> T) -> T {
+        let prev_matches = self.matches.clone();
+        let prev_last_matches = self.last_matches.clone();
+        let prev_match_moves = self.match_moves.clone();
+
+        self.board.swap(first, second);
+        self.recompute_matches_at(&[first, second]);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+
+        self.board.swap(first, second);
+        self.matches = prev_matches;
+        self.last_matches = prev_last_matches;
+        self.match_moves = prev_match_moves;
 
-        if let Some(new_matches) = possible_new_matches {
-            new_matches.into_iter()
-                .filter(|new_match| new_match.iter()
-                    .all(|pos| MatchBoard::<M, P, W, H>::is_pos_unchecked(&pos, start_x, start_y))
-                ).for_each(|new_match| self.matches.push(new_match));
+        match result {
+            Ok(value) => value,
+            Err(cause) => panic::resume_unwind(cause)
         }
     }
 
-    /// Returns true if the given position would not have been checked, assuming all
-    /// positions on the board were iterated over starting with the first column.
+    /// Enables or disables one of the board's patterns. Disabled patterns are skipped when
+    /// looking for matches and match moves. Existing matches and match moves are not updated
+    /// until the positions involved change or [`MatchBoard::rescan_all`] is called.
     ///
     /// # Arguments
     ///
-    /// * `pos` - position to check
-    /// * `start_x` - x-coordinate of the position currently being checked
-    /// * `start_y` - y-coordinate of the position currently being checked
-    fn is_pos_unchecked(pos: &Pos<W, H>, start_x: usize, start_y: usize) -> bool {
-        pos.x() > start_x || (pos.x() == start_x && pos.y() >= start_y)
+    /// * `index` - index of the pattern in the list of patterns the board was created with
+    /// * `enabled` - whether the pattern should be used
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no pattern at the given index.
+    pub fn set_pattern_enabled(&mut self, index: usize, enabled: bool) {
+        self.enabled_patterns[index] = enabled;
     }
 
-    /// Checks for a pattern that includes a specific position on the board. Looks
-    /// for all variants of a pattern (all possible patterns that include the required
-    /// position). Returns the positions on the board that correspond to that pattern
-    /// if there is a match.
+    /// Discards all current matches and match moves and finds them again by scanning the
+    /// whole board.
+    pub fn rescan_all(&mut self) {
+        self.matches.clear();
+        self.last_matches.clear();
+        self.match_moves.iter_mut().for_each(|match_move| *match_move = None);
+        self.add_initial_matches();
+    }
+
+    /// Clears all matches and match moves and finds them again by scanning the whole board,
+    /// exactly like [`MatchBoard::rescan_all`], but checks fixed patterns for different parts
+    /// of the board on multiple threads. The results are combined in the same order as the
+    /// single-threaded scan, so both produce identical matches and match moves. Only worth
+    /// using for very large boards.
+    #[cfg(feature = "rayon")]
+    pub fn rescan_all_parallel(&mut self) where M: Send + Sync, P: Send + Sync {
+        use rayon::prelude::*;
+
+        let this = &*self;
+        let found: Vec<_> = (0..(W * H))
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) = (index / H, index % H);
+                (this.find_matches_after(x, y, Pos::new(x, y)), this.find_match_move(x, y))
+            })
+            .collect();
+
+        self.matches.clear();
+        self.last_matches.clear();
+
+        for (index, (new_matches, match_move)) in found.into_iter().enumerate() {
+            let pos = Pos::new(index / H, index % H);
+
+            // Run matches depend on the runs already found, so they are added in scan order
+            self.matches.extend(new_matches);
+            self.add_run_matches(pos);
+            self.match_moves[index] = match_move;
+        }
+    }
+
+    /// Sets whether [`MatchBoard::try_swap`] rejects swaps where either position contains air.
+    /// Swapping with an empty space only moves the gap, which most games do not allow.
+    /// Swaps with air are allowed by default.
     ///
     /// # Arguments
     ///
-    /// * `pattern` - the match pattern to check
-    /// * `pos` - the position that must be included in a match
-    fn check_pattern<'a>(&self, pattern: &'a MatchPattern<M, W, H>, pos: Pos<W, H>) -> Vec<Match<'a, M, W, H>> {
-        pattern.iter().filter_map(
-            |&original| match pos - original {
-                Ok(origin) => self.check_variant(pattern, origin),
-                Err(_) => None
-            }
-        ).map(|positions| Match::new(pattern, pos, positions)).collect()
+    /// * `require_both_occupied` - whether both positions of a swap must contain a piece
+    pub fn set_require_both_occupied(&mut self, require_both_occupied: bool) {
+        self.require_both_occupied = require_both_occupied;
     }
 
-    /// Checks for a single variant of a pattern and returns the corresponding positions
-    /// on the board if found.
+    /// Swaps two pieces only if the move is valid: the positions must be adjacent (including
+    /// diagonally if the board allows diagonal swaps), must not be separated by a barrier,
+    /// must both contain pieces if [`MatchBoard::set_require_both_occupied`] is enabled,
+    /// and the swap must create a match that includes one of them. The board is not changed
+    /// if the swap is invalid.
     ///
     /// # Arguments
     ///
-    /// * `pattern` - the match pattern to check
-    /// * `new_origin` - the origin to use for the pattern positions so that they
-    ///                  correspond to actual positions on the board
-    fn check_variant(&self, pattern: &MatchPattern<M, W, H>, new_origin: Pos<W, H>) -> Option<HashSet<Pos<W, H>>> {
-        let grid_pos = MatchBoard::<M, P, W, H>::change_origin(pattern.iter(), new_origin)?;
-        let all_match = grid_pos.iter().all(
-            |&pos| MatchBoard::<M, P, W, H>::piece_matches(pattern.match_type(), self.board.piece(pos))
-        );
-        match all_match {
-            true => Some(grid_pos),
-            false => None
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidSwap`] with both positions if the swap is not valid.
+    pub fn try_swap(&mut self, first: Pos<W, H>, second: Pos<W, H>) -> Result<(), InvalidSwap<W, H>> {
+        let is_adjacent = MatchBoard::<M, P, W, H>::adjacent_pos(first, self.allow_diagonal_swaps)
+            .any(|pos| pos == second);
+
+        let is_valid = is_adjacent && self.occupancy_allows_swap(first, second) && !self.board.has_barrier_between(first, second)
+            && self.with_swap(first, second, |swapped| swapped.matches.iter()
+                .any(|new_match| new_match.contains(first) || new_match.contains(second)));
+
+        match is_valid {
+            true => {
+                self.swap(first, second);
+                Ok(())
+            },
+            false => Err(InvalidSwap { first, second })
         }
     }
 
-    /// Checks for a close match on a pattern that includes a specific position on the board.
-    /// Looks for all variants of a pattern (all possible patterns that include the required
-    /// position). Returns the positions on the board that correspond to that pattern
-    /// if there is a match.
+    /// Gets all positions that are part of at least one current match. These are the positions
+    /// [`MatchBoard::remove_matches`] would clear, but the board is not changed.
+    pub fn clear_preview(&self) -> HashSet<Pos<W, H>> {
+        self.matches.iter()
+            .flat_map(|current_match| current_match.iter().copied())
+            .collect()
+    }
+
+    /// Marks positions as inert or not. Inert positions are never part of a match or a match
+    /// move, regardless of the piece there, and gravity in [`MatchBoard::step`] neither moves
+    /// their contents nor moves other pieces through them. Matches are updated for every
+    /// changed position.
     ///
     /// # Arguments
     ///
-    /// * `pattern` - the match pattern to check
-    /// * `pos` - the position that must be included in a match
-    fn check_close_pattern<'a>(&self, pattern: &'a MatchPattern<M, W, H>, pos: Pos<W, H>) -> Option<MatchMove<'a, M, W, H>> {
-        pattern.iter().find_map(
-            |&original| match pos - original {
-                Ok(origin) => self.check_close_variant(pattern, origin),
-                Err(_) => None
+    /// * `region` - the positions to mark
+    /// * `inert` - whether the positions should be inert
+    pub fn set_inert(&mut self, region: impl IntoIterator<Item=Pos<W, H>>, inert: bool) {
+        for pos in region {
+            if self.inert.get(pos) != inert {
+                self.inert.set(pos, inert);
+                self.recompute_matches(pos);
             }
-        )
+        }
     }
 
-    /// Checks for a close match of a single variant of a pattern and returns the
-    /// corresponding positions on the board if found.
+    /// Checks whether a position is inert. See [`MatchBoard::set_inert`].
     ///
     /// # Arguments
     ///
-    /// * `pattern` - the match pattern to check
-    /// * `new_origin` - the origin to use for the pattern positions so that they
-    ///                  correspond to actual positions on the board
-    fn check_close_variant<'a>(&self, pattern: &'a MatchPattern<M, W, H>, new_origin: Pos<W, H>) -> Option<MatchMove<'a, M, W, H>> {
-        let grid_pos = MatchBoard::<M, P, W, H>::change_origin(pattern.iter(), new_origin)?;
+    /// * `pos` - the position to check
+    pub fn is_inert(&self, pos: Pos<W, H>) -> bool {
+        self.inert.get(pos)
+    }
 
-        let (matched, unmatched): (HashSet<Pos<W, H>>, HashSet<Pos<W, H>>) = grid_pos.iter().partition(
-            |&&pos| MatchBoard::<M, P, W, H>::piece_matches(pattern.match_type(), self.board.piece(pos))
-        );
+    /// Takes all current matches out of the board without changing any pieces, leaving no
+    /// current matches. The pieces that formed the matches are not checked again until they
+    /// change or [`MatchBoard::rescan_all`] is called. Returns the matches that were taken,
+    /// in the same order as [`MatchBoard::matches`].
+    pub fn take_matches(&mut self) -> Vec<OwnedMatch<M, W, H>> {
+        self.last_matches.clear();
+        self.matches.drain(..).map(|taken_match| OwnedMatch::from(&taken_match)).collect()
+    }
 
-        if unmatched.len() != 1 {
-            return None;
+    /// Removes all current matches by replacing every piece in a match with air. Returns the
+    /// positions that were cleared.
+    pub fn remove_matches(&mut self) -> HashSet<Pos<W, H>> {
+        let cleared = self.clear_preview();
+
+        let cleared_positions: Vec<Pos<W, H>> = cleared.iter().copied().collect();
+        for &pos in cleared_positions.iter() {
+            self.board.set_piece(pos, P::AIR);
         }
 
-        let missing_pos = unmatched.into_iter().next().unwrap();
-        let match_type = pattern.match_type();
+        self.recompute_matches_at(&cleared_positions);
 
-        let completing_pos = MatchBoard::<M, P, W, H>::adjacent_pos(missing_pos)
-            .find(|&completing_pos| !matched.contains(&completing_pos)
-                && !self.board.has_barrier_between(completing_pos, missing_pos)
-                && MatchBoard::<M, P, W, H>::piece_matches(match_type, self.board.piece(completing_pos)));
+        let mut sorted_cleared: Vec<Pos<W, H>> = cleared.iter().copied().collect();
+        sorted_cleared.sort();
+        self.record(BoardEvent::RemoveMatches(sorted_cleared));
 
-        completing_pos.map(|comp_pos| MatchMove::new(pattern, missing_pos, comp_pos, matched))
+        cleared
     }
 
-    /// Returns an iterator of all positions directly adjacent to the given position.
+    /// Runs one round of a turn: removes all current matches, applies gravity to the board,
+    /// and refills the empty spaces open to the top of the board. Inert positions are left as
+    /// they are by both gravity and the refill. Matches are updated
+    /// afterward, so any cascade they cause is ready for the next call. Only one round runs,
+    /// so each cascade can be shown separately.
     ///
     /// # Arguments
     ///
-    /// * `pos` - position to get the adjacent positions of
-    fn adjacent_pos(pos: Pos<W, H>) -> impl Iterator<Item=Pos<W, H>> {
-        let horizontal_offset = Pos::new(1, 0);
-        let vertical_offset = Pos::new(0, 1);
-
-        let mut adjacent = Vec::with_capacity(4);
-
-        // Left
-        if let Ok(left_pos) = pos - horizontal_offset {
-            adjacent.push(left_pos);
-        }
-
-        // Right
-        if let Ok(right_pos) = pos + horizontal_offset {
-            adjacent.push(right_pos);
+    /// * `spawn` - creates the new piece for an empty position, as in
+    ///             [`BoardState::refill_from`]
+    #[allow(clippy::doc_overindented_list_items)]
+    pub fn step<F: FnMut(Pos<W, H>) -> P>(&mut self, spawn: F) -> StepResult<P, W, H> {
+        let cleared = self.remove_matches();
+
+        let moves = self.board.apply_gravity_skipping(GravityBias::Left, &self.inert);
+        for &(first, second) in moves.iter() {
+            self.record(BoardEvent::Swap(first, second));
         }
 
-        // Below
-        if let Ok(below_pos) = pos - vertical_offset {
-            adjacent.push(below_pos);
+        let spawned: Vec<(Pos<W, H>, P)> = self.board.refill_skipping(Direction::North, spawn, &self.inert).into_iter()
+            .map(|pos| (pos, self.board.piece(pos)))
+            .collect();
+        for &(pos, piece) in spawned.iter() {
+            self.record(BoardEvent::SetPiece(pos, piece));
         }
 
-        // Above
-        if let Ok(above_pos) = pos + vertical_offset {
-            adjacent.push(above_pos);
-        }
+        let mut unique_positions = HashSet::new();
+        let changed_positions: Vec<Pos<W, H>> = moves.iter()
+            .flat_map(|&(first, second)| [first, second])
+            .chain(spawned.iter().map(|&(pos, _)| pos))
+            .filter(|&pos| unique_positions.insert(pos))
+            .collect();
+        self.recompute_matches_at(&changed_positions);
 
-        adjacent.into_iter()
+        StepResult { cleared, moves, spawned }
     }
 
-    /// Changes the origin of a set of points.
+    /// Replaces the pieces at all the given positions with air and then updates matches once for
+    /// all of them. Returns how many of the positions did not already contain air.
     ///
     /// # Arguments
     ///
-    /// * `positions` - the positions to change the origin of
-    /// * `origin` - the new origin to use for the positions
-    fn change_origin<'a>(positions: impl Iterator<Item=&'a Pos<W, H>>, origin: Pos<W, H>) -> Option<HashSet<Pos<W, H>>> {
-        let mut new_positions = HashSet::new();
+    /// * `positions` - the positions to clear. Repeated positions are only cleared once.
+    pub fn clear_positions(&mut self, positions: impl IntoIterator<Item=Pos<W, H>>) -> usize {
+        let mut unique_positions = HashSet::new();
+        let cleared_positions: Vec<Pos<W, H>> = positions.into_iter()
+            .filter(|&pos| unique_positions.insert(pos))
+            .collect();
 
-        for &pos in positions {
-            if let Ok(new_pos) = pos + origin {
-                new_positions.insert(new_pos);
-            } else {
-                return None;
+        let mut cleared_count = 0;
+        for &pos in cleared_positions.iter() {
+            if self.board.set_piece(pos, P::AIR) != P::AIR {
+                cleared_count += 1;
             }
+
+            self.record(BoardEvent::SetPiece(pos, P::AIR));
         }
 
-        Some(new_positions)
-    }
+        self.recompute_matches_at(&cleared_positions);
 
-    /// Checks if the given piece has the given match type.
-    ///
-    /// # Arguments
-    ///
-    /// * `match_type` - match type to compare to the piece
-    /// * `piece` - piece to compare to the match type
-    fn piece_matches(match_type: M, piece: P) -> bool {
-        let type_piece: P = match_type.into();
-        (type_piece & piece) != P::AIR
+        cleared_count
     }
 
-}
+    /// Removes all current matches like [`MatchBoard::remove_matches`] and counts how many
+    /// pieces were cleared for each match type. Each cleared position is counted once, under the
+    /// match type of the first current match that contains it.
+    pub fn remove_matches_counted(&mut self) -> HashMap<M, usize> where M: Eq + Hash {
+        let mut counted = HashSet::new();
+        let mut counts = HashMap::new();
+
+        for current_match in self.matches.iter() {
+            for &pos in current_match.iter() {
+                if counted.insert(pos) {
+                    *counts.entry(current_match.pattern().match_type()).or_insert(0) += 1;
+                }
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use std::ops::BitAnd;
-    use crate::{BoardState, MatchBoard, MatchPattern, Piece, Pos};
+        self.remove_matches();
 
-    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-    enum TestMatchType {
-        First = 0b0,
-        Second = 0b1
+        counts
     }
 
-    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
-    enum TestPiece {
+    /// Returns all changes recorded since the log was last taken, in the order they were made,
+    /// and clears the log. Always returns an empty log if logging was not enabled when the
+    /// board was created.
+    pub fn take_log(&mut self) -> Vec<BoardEvent<P, W, H>> {
+        self.log.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Gets all matches on the board. Matches are always based on the current board
+    /// state.
+    pub fn matches(&self) -> &[Match<'_, M, W, H>] {
+        &self.matches[..]
+    }
+
+    /// Gets the current matches that were caused by the most recent change to the board, such
+    /// as the last swap or the last piece set. Matches that were already on the board before
+    /// that change are not included. Empty until the board changes and after a full rescan.
+    pub fn last_matches(&self) -> &[Match<'_, M, W, H>] {
+        &self.last_matches[..]
+    }
+
+    /// Classifies every current match by the shape of its positions, in the same order as
+    /// [`MatchBoard::matches`]. Matches with no positions are skipped.
+    pub fn special_matches(&self) -> Vec<SpecialMatch<W, H>> {
+        self.matches.iter()
+            .filter(|current_match| current_match.iter().next().is_some())
+            .map(|current_match| {
+                let positions = current_match.sorted_positions();
+                let (shape, pivot) = MatchBoard::<M, P, W, H>::classify_shape(&positions)
+                    .unwrap_or((MatchShape::Other, None));
+
+                SpecialMatch { shape, pivot: pivot.unwrap_or_else(|| current_match.center()), positions }
+            })
+            .collect()
+    }
+
+    /// Gets all current matches that contain at least one of the given positions. For example,
+    /// passing the positions filled by a refill finds the matches caused by the new pieces.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions that each returned match must touch
+    pub fn matches_involving(&self, positions: &HashSet<Pos<W, H>>) -> Vec<&Match<'_, M, W, H>> {
+        self.matches.iter()
+            .filter(|current_match| current_match.iter().any(|pos| positions.contains(pos)))
+            .collect()
+    }
+
+    /// Gets all current matches with at least one position inside a rectangle. Since larger
+    /// y-coordinates are higher on the board, the top left corner has the smallest x-coordinate
+    /// and the largest y-coordinate. The rectangle includes its edges.
+    ///
+    /// # Arguments
+    ///
+    /// * `top_left` - the top left corner of the rectangle
+    /// * `bottom_right` - the bottom right corner of the rectangle
+    pub fn matches_in_region(&self, top_left: Pos<W, H>, bottom_right: Pos<W, H>) -> Vec<&Match<'_, M, W, H>> {
+        let min_x = top_left.x().min(bottom_right.x());
+        let max_x = top_left.x().max(bottom_right.x());
+        let min_y = top_left.y().min(bottom_right.y());
+        let max_y = top_left.y().max(bottom_right.y());
+
+        self.matches.iter()
+            .filter(|current_match| current_match.iter().any(|pos| (min_x..=max_x).contains(&pos.x())
+                && (min_y..=max_y).contains(&pos.y())))
+            .collect()
+    }
+
+    /// Groups all current matches by the pattern that produced them. Each group is keyed by the
+    /// index of its pattern in the list of patterns the board was created with. Patterns without
+    /// any current matches have no group.
+    pub fn matches_by_pattern(&self) -> HashMap<usize, Vec<&Match<'_, M, W, H>>> {
+        let mut groups: HashMap<usize, Vec<&Match<'_, M, W, H>>> = HashMap::new();
+
+        for current_match in self.matches.iter() {
+            groups.entry(current_match.pattern_index()).or_default().push(current_match);
+        }
+
+        groups
+    }
+
+    /// Counts how many current matches include each position. Positions in several
+    /// overlapping matches have higher counts, and positions in no matches are left out.
+    pub fn match_heat(&self) -> HashMap<Pos<W, H>, usize> {
+        let mut heat = HashMap::new();
+
+        for &pos in self.matches.iter().flat_map(|current_match| current_match.iter()) {
+            *heat.entry(pos).or_insert(0) += 1;
+        }
+
+        heat
+    }
+
+    /// Gets all available moves on the board that create a match, where one piece needs to
+    /// change to create a match.
+    pub fn match_moves(&self) -> Vec<MatchMove<'_, M, W, H>> {
+        self.match_moves.iter().flatten().cloned().collect()
+    }
+
+    /// Counts the distinct moves available on the board. Moves from [`MatchBoard::match_moves`]
+    /// that share a missing position count once, since filling that position has the same
+    /// result no matter which pattern found it.
+    pub fn move_count(&self) -> usize {
+        self.threat_cells().len()
+    }
+
+    /// Gets every position that is one piece away from completing a match, which is the
+    /// missing position of each move from [`MatchBoard::match_moves`].
+    pub fn threat_cells(&self) -> HashSet<Pos<W, H>> {
+        self.match_moves.iter()
+            .flatten()
+            .map(|match_move| match_move.missing_pos())
+            .collect()
+    }
+
+    /// Checks whether a match can be made with at most `depth` swaps of adjacent pieces that
+    /// are not separated by a barrier. Swaps with air are skipped if
+    /// [`MatchBoard::set_require_both_occupied`] is enabled. Matches from both patterns and
+    /// run patterns count. With a depth of zero, this only checks whether there is a match on
+    /// the board already. The board is not changed.
+    ///
+    /// Every other depth tries each swap on a copy of the board and searches again from there,
+    /// so the cost grows exponentially with the depth: each extra swap multiplies the work by
+    /// roughly twice the number of positions on the board. Keep the depth small.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - the maximum number of swaps to make
+    pub fn has_move_within(&self, depth: usize) -> bool {
+        if !self.matches.is_empty() {
+            return true;
+        }
+
+        if depth == 0 {
+            return false;
+        }
+
+        self.adjacent_swaps().into_iter()
+            .filter(|&(first, second)| self.occupancy_allows_swap(first, second))
+            .any(|(first, second)| {
+                let mut next_board = self.clone();
+                next_board.log = None;
+                next_board.swap(first, second);
+                next_board.has_move_within(depth - 1)
+            })
+    }
+
+    /// Finds all swaps of adjacent pieces, not separated by a barrier, that would break at least
+    /// one current match by moving a piece that does not fit the match's type into it. The board
+    /// is not changed.
+    pub fn match_breaking_swaps(&self) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        self.adjacent_swaps().into_iter()
+            .filter(|&(first, second)| self.matches.iter().any(|current_match| {
+                let match_type = current_match.pattern().match_type();
+                let breaks_at = |pos: Pos<W, H>, new_piece: P| current_match.contains(pos)
+                    && !MatchBoard::<M, P, W, H>::piece_matches(match_type, new_piece);
+
+                breaks_at(first, self.board.piece(second)) || breaks_at(second, self.board.piece(first))
+            }))
+            .collect()
+    }
+
+    /// Finds every placement of a pattern that fits entirely within the board, regardless of
+    /// the pieces currently on the board. Each placement is the set of board positions the
+    /// pattern would cover.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the pattern to place on the board
+    pub fn pattern_placements(&self, pattern: &MatchPattern<M, W, H>) -> Vec<HashSet<Pos<W, H>>> {
+        (0..W).flat_map(|x| (0..H).map(move |y| Pos::new(x, y)))
+            .filter_map(|origin| pattern.at_origin(origin))
+            .collect()
+    }
+
+    /// Checks whether the board is stable: no pieces would fall under gravity and there are
+    /// no matches left to handle. Pieces in or above inert positions do not fall.
+    pub fn is_settled(&self) -> bool {
+        self.matches.is_empty() && self.board.floating_pieces().into_iter().all(|pos| self.is_inert(pos)
+            || pos.adjacent(Direction::South).map(|below| self.is_inert(below)).unwrap_or(true))
+    }
+
+    /// Finds all groups of identical pieces that form a horizontal or vertical line of at least
+    /// `min_run` pieces, without using any match patterns. Lines that share a position are
+    /// merged into a single group, so shapes like an L or a plus are returned as one match.
+    /// Barriers break lines, and air never forms a match.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_run` - the minimum number of identical pieces in a line for it to match
+    pub fn classic_matches(&self, min_run: usize) -> Vec<HashSet<Pos<W, H>>> {
+        let mut runs = Vec::new();
+
+        if W == 0 || H == 0 {
+            return Vec::new();
+        }
+
+        for y in 0..H {
+            runs.append(&mut self.line_runs(Pos::new(0, y), Direction::East, min_run));
+        }
+
+        for x in 0..W {
+            runs.append(&mut self.line_runs(Pos::new(x, 0), Direction::North, min_run));
+        }
+
+        let mut groups: Vec<HashSet<Pos<W, H>>> = Vec::new();
+        for run in runs {
+            let (overlapping, mut disjoint): (Vec<_>, Vec<_>) = groups.into_iter()
+                .partition(|group| !group.is_disjoint(&run));
+
+            let merged = overlapping.into_iter().fold(run, |mut merged, group| {
+                merged.extend(group);
+                merged
+            });
+
+            disjoint.push(merged);
+            groups = disjoint;
+        }
+
+        groups
+    }
+
+    /// Scans the initial state of the board for matches and close matches.
+    fn add_initial_matches(&mut self) {
+        for x in 0..W {
+            for y in 0..H {
+                self.add_matches_after(x, y, Pos::new(x, y));
+                self.add_run_matches(Pos::new(x, y));
+                self.rescan_match_move(x, y);
+            }
+        };
+    }
+
+    /// Adds an event to the log if logging is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - the change made to the board
+    fn record(&mut self, event: BoardEvent<P, W, H>) {
+        if let Some(log) = self.log.as_mut() {
+            log.push(event);
+        }
+    }
+
+    /// Recomputes the current set of matches when a position on the board is changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `changed_pos` - the position on the board that changed
+    fn recompute_matches(&mut self, changed_pos: Pos<W, H>) {
+        self.recompute_matches_at(&[changed_pos]);
+    }
+
+    /// Recomputes the current set of matches once after several positions on the board changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `changed_positions` - the positions on the board that changed
+    fn recompute_matches_at(&mut self, changed_positions: &[Pos<W, H>]) {
+        if changed_positions.is_empty() {
+            return;
+        }
+
+        for &changed_pos in changed_positions {
+
+            // TODO: replace with drain_filter() once it is stable
+            self.matches = self.matches.clone().into_iter()
+                .filter(|prev_match| !prev_match.contains(changed_pos))
+                .collect();
+
+            self.add_matches_after(0, 0, changed_pos);
+            self.add_run_matches(changed_pos);
+
+        }
+
+        self.last_matches = self.matches.iter()
+            .filter(|new_match| changed_positions.contains(&new_match.changed_pos()))
+            .cloned()
+            .collect();
+
+        // Only close matches near the changed positions can use the pieces there
+        let (reach_x, reach_y) = self.match_move_reach();
+        let min_x = changed_positions.iter().map(|pos| pos.x()).min().unwrap().saturating_sub(reach_x);
+        let max_x = changed_positions.iter().map(|pos| pos.x()).max().unwrap().saturating_add(reach_x).min(W - 1);
+        let min_y = changed_positions.iter().map(|pos| pos.y()).min().unwrap().saturating_sub(reach_y);
+        let max_y = changed_positions.iter().map(|pos| pos.y()).max().unwrap().saturating_add(reach_y).min(H - 1);
+
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.rescan_match_move(x, y);
+            }
+        }
+    }
+
+    /// Updates the cached match move found when scanning the given position, assuming all
+    /// positions before it have already been scanned.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x-coordinate of the position to scan
+    /// * `y` - y-coordinate of the position to scan
+    fn rescan_match_move(&mut self, x: usize, y: usize) {
+        self.match_moves[x * H + y] = self.find_match_move(x, y);
+    }
+
+    /// Finds the match move that [`MatchBoard::rescan_match_move`] would cache for the given
+    /// position, without caching it.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x-coordinate of the position to scan
+    /// * `y` - y-coordinate of the position to scan
+    fn find_match_move(&self, x: usize, y: usize) -> Option<MatchMove<'a, M, W, H>> {
+        let possible_match_move = self.patterns.iter()
+            .zip(self.enabled_patterns.iter())
+            .filter(|&(_, &enabled)| enabled)
+            .find_map(|(&pattern, _)| self.check_close_pattern(pattern, Pos::new(x, y)));
+
+        possible_match_move.filter(|match_move| {
+            match_move.iter().all(|pos| MatchBoard::<M, P, W, H>::is_pos_unchecked(pos, x, y))
+                && MatchBoard::<M, P, W, H>::is_pos_unchecked(&match_move.missing_pos(), x, y)
+        })
+    }
+
+    /// Finds how far away, horizontally and vertically, a changed position can be from a
+    /// scanned position while still affecting the match move found there. This covers the
+    /// widest and tallest pattern plus the adjacent completing position.
+    fn match_move_reach(&self) -> (usize, usize) {
+        self.patterns.iter().fold((1, 1), |(reach_x, reach_y), pattern| {
+            let span = |coord: fn(&Pos<W, H>) -> usize| {
+                let min = pattern.iter().map(coord).min().unwrap_or(0);
+                let max = pattern.iter().map(coord).max().unwrap_or(0);
+                max - min + 1
+            };
+
+            (reach_x.max(span(Pos::x)), reach_y.max(span(Pos::y)))
+        })
+    }
+
+    /// Adds matches for the given changed position that have not already been found, assuming
+    /// positions before `(start_x, start_y)` have already been checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_x` - x-coordinate of the position currently being checked
+    /// * `start_y` - y-coordinate of the position currently being checked
+    /// * `changed_pos` - position on the board that was changed
+    fn add_matches_after(&mut self, start_x: usize, start_y: usize, changed_pos: Pos<W, H>) {
+        let new_matches = self.find_matches_after(start_x, start_y, changed_pos);
+        self.matches.extend(new_matches);
+    }
+
+    /// Finds the matches that [`MatchBoard::add_matches_after`] would add, without adding them.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_x` - x-coordinate of the position currently being checked
+    /// * `start_y` - y-coordinate of the position currently being checked
+    /// * `changed_pos` - position on the board that was changed
+    fn find_matches_after(&self, start_x: usize, start_y: usize, changed_pos: Pos<W, H>) -> Vec<Match<'a, M, W, H>> {
+        let possible_new_matches = self.patterns.iter()
+            .zip(self.enabled_patterns.iter())
+            .enumerate()
+            .filter(|&(_, (_, &enabled))| enabled)
+            .find_map(|(pattern_index, (&pattern, _))| {
+                let new_matches = self.check_pattern(pattern, pattern_index, changed_pos);
+                match new_matches.is_empty() {
+                    true => None,
+                    false => Some(new_matches)
+                }
+            });
+
+        possible_new_matches.unwrap_or_default().into_iter()
+            .filter(|new_match| new_match.iter()
+                .all(|pos| MatchBoard::<M, P, W, H>::is_pos_unchecked(pos, start_x, start_y))
+            ).collect()
+    }
+
+    /// Adds matches for the longest lines that match a run pattern and include or are next to
+    /// the given changed position. Each line replaces any earlier match from the same run
+    /// pattern that overlaps it, so a line that grows is never matched twice. Checking the
+    /// neighbors finds what is left of a line that was split at the changed position.
+    ///
+    /// # Arguments
+    ///
+    /// * `changed_pos` - position on the board that was changed
+    fn add_run_matches(&mut self, changed_pos: Pos<W, H>) {
+        let this = &*self;
+        let new_runs: Vec<_> = this.run_patterns.iter().enumerate().flat_map(|(run_index, &run_pattern)| {
+            let (backward, forward) = match run_pattern.orientation() {
+                Orientation::Horizontal => (Direction::West, Direction::East),
+                Orientation::Vertical => (Direction::South, Direction::North)
+            };
+
+            let candidates = [changed_pos.adjacent(backward).ok(), Some(changed_pos), changed_pos.adjacent(forward).ok()];
+
+            candidates.into_iter().flatten().filter_map(move |candidate| {
+                let is_run_piece = |pos: Pos<W, H>| !this.is_inert(pos)
+                    && MatchBoard::<M, P, W, H>::piece_matches(run_pattern.match_type(), this.board.piece(pos));
+
+                if !is_run_piece(candidate) {
+                    return None;
+                }
+
+                let mut start = candidate;
+                while let Some(prev_pos) = start.adjacent(backward).ok().filter(|&pos| is_run_piece(pos)) {
+                    start = prev_pos;
+                }
+
+                let mut positions = HashSet::from([start]);
+                let mut end = start;
+                while let Some(next_pos) = end.adjacent(forward).ok().filter(|&pos| is_run_piece(pos)) {
+                    positions.insert(next_pos);
+                    end = next_pos;
+                }
+
+                match positions.len() >= run_pattern.min_len() {
+                    true => Some(Match::new(
+                        run_pattern.line(positions.len()),
+                        this.patterns.len() + run_index,
+                        changed_pos,
+                        positions
+                    )),
+                    false => None
+                }
+            })
+        }).collect();
+
+        for new_run in new_runs {
+            let is_duplicate = self.matches.iter().any(|prev_match| prev_match.pattern_index() == new_run.pattern_index()
+                && prev_match.iter().count() == new_run.iter().count()
+                && new_run.iter().all(|&pos| prev_match.contains(pos)));
+
+            if is_duplicate {
+                continue;
+            }
+
+            self.matches.retain(|prev_match| prev_match.pattern_index() != new_run.pattern_index()
+                || !prev_match.intersects(&new_run));
+            self.matches.push(new_run);
+        }
+    }
+
+    /// Checks whether two positions hold pieces that may be swapped, which is always true
+    /// unless [`MatchBoard::set_require_both_occupied`] is enabled and either one contains air.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    fn occupancy_allows_swap(&self, first: Pos<W, H>, second: Pos<W, H>) -> bool {
+        !self.require_both_occupied
+            || (self.board.piece(first) != P::AIR && self.board.piece(second) != P::AIR)
+    }
+
+    /// Finds all pairs of adjacent positions that are not separated by a barrier and hold
+    /// different pieces, so that swapping them would change the board. Diagonal pairs are
+    /// included if the board allows diagonal swaps.
+    fn adjacent_swaps(&self) -> Vec<(Pos<W, H>, Pos<W, H>)> {
+        let mut swaps = Vec::new();
+
+        for x in 0..W {
+            for y in 0..H {
+                let pos = Pos::new(x, y);
+
+                let orthogonal = [Direction::East, Direction::North].into_iter()
+                    .filter_map(|dir| pos.adjacent(dir).ok());
+                let diagonal = [x.checked_sub(1), Some(x + 1)].into_iter()
+                    .filter(|_| self.allow_diagonal_swaps)
+                    .filter_map(|next_x| Pos::try_new(next_x?, y + 1).ok());
+
+                for next_pos in orthogonal.chain(diagonal) {
+                    if self.board.piece(pos) != self.board.piece(next_pos)
+                        && !self.board.has_barrier_between(pos, next_pos) {
+                        swaps.push((pos, next_pos));
+                    }
+                }
+            }
+        }
+
+        swaps
+    }
+
+    /// Finds all lines of at least `min_run` identical, non-air pieces along one row or column.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - the position at the edge of the board where the row or column begins
+    /// * `dir` - the direction to scan in from `start`
+    /// * `min_run` - the minimum number of identical pieces in a line
+    fn line_runs(&self, start: Pos<W, H>, dir: Direction, min_run: usize) -> Vec<HashSet<Pos<W, H>>> {
+        let mut runs = Vec::new();
+        let mut run = vec![start];
+        let mut prev_pos = start;
+
+        loop {
+            let next_pos = prev_pos.adjacent(dir).ok();
+            let continues_run = next_pos.map(|pos| !self.board.has_barrier_between(prev_pos, pos)
+                && self.board.piece(pos) == self.board.piece(prev_pos))
+                .unwrap_or(false);
+
+            if !continues_run {
+                if run.len() >= min_run && self.board.piece(prev_pos) != P::AIR {
+                    runs.push(run.iter().copied().collect());
+                }
+
+                run.clear();
+            }
+
+            match next_pos {
+                Some(pos) => {
+                    run.push(pos);
+                    prev_pos = pos;
+                },
+                None => break
+            }
+        }
+
+        runs
+    }
+
+    /// Returns true if the given position would not have been checked, assuming all
+    /// positions on the board were iterated over starting with the first column.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - position to check
+    /// * `start_x` - x-coordinate of the position currently being checked
+    /// * `start_y` - y-coordinate of the position currently being checked
+    fn is_pos_unchecked(pos: &Pos<W, H>, start_x: usize, start_y: usize) -> bool {
+        pos.x() > start_x || (pos.x() == start_x && pos.y() >= start_y)
+    }
+
+    /// Checks for a pattern that includes a specific position on the board. Looks
+    /// for all variants of a pattern (all possible patterns that include the required
+    /// position). Returns the positions on the board that correspond to that pattern
+    /// if there is a match.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the match pattern to check
+    /// * `pattern_index` - the index of the pattern in the board's list of patterns
+    /// * `pos` - the position that must be included in a match
+    fn check_pattern<'b>(&self, pattern: &'b MatchPattern<M, W, H>, pattern_index: usize,
+                         pos: Pos<W, H>) -> Vec<Match<'b, M, W, H>> {
+        pattern.iter().filter_map(
+            |&original| match pos - original {
+                Ok(origin) => self.check_variant(pattern, origin),
+                Err(_) => None
+            }
+        ).map(|positions| Match::new(pattern, pattern_index, pos, positions)).collect()
+    }
+
+    /// Checks for a single variant of a pattern and returns the corresponding positions
+    /// on the board if found.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the match pattern to check
+    /// * `new_origin` - the origin to use for the pattern positions so that they
+    ///                  correspond to actual positions on the board
+    #[allow(clippy::doc_overindented_list_items)]
+    fn check_variant(&self, pattern: &MatchPattern<M, W, H>, new_origin: Pos<W, H>) -> Option<HashSet<Pos<W, H>>> {
+        let grid_pos = pattern.at_origin(new_origin)?;
+        let all_match = grid_pos.iter().all(
+            |&pos| !self.is_inert(pos)
+                && MatchBoard::<M, P, W, H>::piece_matches(pattern.match_type(), self.board.piece(pos))
+        );
+        match all_match {
+            true => Some(grid_pos),
+            false => None
+        }
+    }
+
+    /// Checks for a close match on a pattern that includes a specific position on the board.
+    /// Looks for all variants of a pattern (all possible patterns that include the required
+    /// position). Returns the positions on the board that correspond to that pattern
+    /// if there is a match.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the match pattern to check
+    /// * `pos` - the position that must be included in a match
+    fn check_close_pattern<'b>(&self, pattern: &'b MatchPattern<M, W, H>, pos: Pos<W, H>) -> Option<MatchMove<'b, M, W, H>> {
+        pattern.iter().find_map(
+            |&original| match pos - original {
+                Ok(origin) => self.check_close_variant(pattern, origin),
+                Err(_) => None
+            }
+        )
+    }
+
+    /// Checks for a close match of a single variant of a pattern and returns the
+    /// corresponding positions on the board if found.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the match pattern to check
+    /// * `new_origin` - the origin to use for the pattern positions so that they
+    ///                  correspond to actual positions on the board
+    #[allow(clippy::doc_overindented_list_items)]
+    fn check_close_variant<'b>(&self, pattern: &'b MatchPattern<M, W, H>, new_origin: Pos<W, H>) -> Option<MatchMove<'b, M, W, H>> {
+        let grid_pos = pattern.at_origin(new_origin)?;
+
+        if grid_pos.iter().any(|&pos| self.is_inert(pos)) {
+            return None;
+        }
+
+        let (matched, unmatched): (HashSet<Pos<W, H>>, HashSet<Pos<W, H>>) = grid_pos.iter().partition(
+            |&&pos| MatchBoard::<M, P, W, H>::piece_matches(pattern.match_type(), self.board.piece(pos))
+        );
+
+        if unmatched.len() != 1 {
+            return None;
+        }
+
+        let missing_pos = unmatched.into_iter().next().unwrap();
+        let match_type = pattern.match_type();
+
+        // The completing piece must be able to swap directly into the missing position, so it
+        // has to be adjacent with no barrier in between
+        let completing_pos = MatchBoard::<M, P, W, H>::adjacent_pos(missing_pos, self.allow_diagonal_swaps)
+            .find(|&completing_pos| !matched.contains(&completing_pos)
+                && !self.is_inert(completing_pos)
+                && !self.board.has_barrier_between(completing_pos, missing_pos)
+                && MatchBoard::<M, P, W, H>::piece_matches(match_type, self.board.piece(completing_pos)));
+
+        completing_pos.map(|comp_pos| MatchMove::new(pattern, missing_pos, comp_pos, matched))
+    }
+
+    /// Returns an iterator of all positions directly adjacent to the given position. Diagonal
+    /// positions come after all orthogonal positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - position to get the adjacent positions of
+    /// * `include_diagonals` - whether to include the four diagonally adjacent positions
+    fn adjacent_pos(pos: Pos<W, H>, include_diagonals: bool) -> impl Iterator<Item=Pos<W, H>> {
+        let mut adjacent = Vec::with_capacity(4);
+
+        // Left
+        if let Ok(left_pos) = pos.adjacent(Direction::West) {
+            adjacent.push(left_pos);
+        }
+
+        // Right
+        if let Ok(right_pos) = pos.adjacent(Direction::East) {
+            adjacent.push(right_pos);
+        }
+
+        // Below
+        if let Ok(below_pos) = pos.adjacent(Direction::South) {
+            adjacent.push(below_pos);
+        }
+
+        // Above
+        if let Ok(above_pos) = pos.adjacent(Direction::North) {
+            adjacent.push(above_pos);
+        }
+
+        if include_diagonals {
+            for x in [pos.x().checked_sub(1), Some(pos.x() + 1)] {
+                for y in [pos.y().checked_sub(1), Some(pos.y() + 1)] {
+                    if let (Some(x), Some(y)) = (x, y) {
+                        if let Ok(diagonal_pos) = Pos::try_new(x, y) {
+                            adjacent.push(diagonal_pos);
+                        }
+                    }
+                }
+            }
+        }
+
+        adjacent.into_iter()
+    }
+
+    /// Finds the shape of a group of positions and, for intersection shapes, the position where
+    /// the row and column meet. Returns `None` if the shape is not one of the named shapes.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions to classify, which must not be empty
+    fn classify_shape(positions: &[Pos<W, H>]) -> Option<(MatchShape, Option<Pos<W, H>>)> {
+        let span = |coord: fn(&Pos<W, H>) -> usize| {
+            let min = positions.iter().map(coord).min().unwrap_or(0);
+            let max = positions.iter().map(coord).max().unwrap_or(0);
+            (min, max)
+        };
+        let (min_x, max_x) = span(Pos::x);
+        let (min_y, max_y) = span(Pos::y);
+        let (width, height) = (max_x - min_x + 1, max_y - min_y + 1);
+
+        if width == 1 || height == 1 {
+            return Some((MatchShape::Line, None));
+        }
+
+        if width == height && positions.len() == width * height {
+            return Some((MatchShape::Square, None));
+        }
+
+        let pivot = positions.iter().copied()
+            .find(|pivot| positions.iter().all(|pos| pos.x() == pivot.x() || pos.y() == pivot.y()))?;
+
+        let row_xs = positions.iter().filter(|pos| pos.y() == pivot.y()).map(Pos::x);
+        let col_ys = positions.iter().filter(|pos| pos.x() == pivot.x()).map(Pos::y);
+        let is_row_end = pivot.x() == row_xs.clone().min().unwrap() || pivot.x() == row_xs.max().unwrap();
+        let is_col_end = pivot.y() == col_ys.clone().min().unwrap() || pivot.y() == col_ys.max().unwrap();
+
+        let shape = match (is_row_end, is_col_end) {
+            (true, true) => MatchShape::L,
+            (false, false) => MatchShape::Cross,
+            _ => MatchShape::T
+        };
+
+        Some((shape, Some(pivot)))
+    }
+
+    /// Checks if the given piece has the given match type.
+    ///
+    /// # Arguments
+    ///
+    /// * `match_type` - match type to compare to the piece
+    /// * `piece` - piece to compare to the match type
+    fn piece_matches(match_type: M, piece: P) -> bool {
+        let type_piece: P = match_type.into();
+        (type_piece & piece) != P::AIR
+    }
+
+}
+
+#[cfg(test)]
+#[allow(clippy::useless_vec)]
+mod tests {
+    use std::collections::HashSet;
+    use std::ops::BitAnd;
+    use crate::{BoardEvent, BoardState, InvalidSwap, MatchBoard, MatchPattern, MatchShape, Orientation, OwnedMatch, Piece, Pos, RunPattern};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    enum TestMatchType {
+        First = 0b0,
+        Second = 0b1
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+    enum TestPiece {
         #[default]
         None = 0b00,
         First = 0b01,
@@ -364,488 +1412,1873 @@ mod tests {
         Both = 0b11
     }
 
-    impl From<TestMatchType> for TestPiece {
-        fn from(value: TestMatchType) -> Self {
-            match value {
-                TestMatchType::First => TestPiece::First,
-                TestMatchType::Second => TestPiece::Second
-            }
-        }
+    impl From<TestMatchType> for TestPiece {
+        fn from(value: TestMatchType) -> Self {
+            match value {
+                TestMatchType::First => TestPiece::First,
+                TestMatchType::Second => TestPiece::Second
+            }
+        }
+    }
+
+    impl BitAnd for TestPiece {
+        type Output = Self;
+
+        fn bitand(self, rhs: Self) -> Self::Output {
+            match self as u8 & rhs as u8 {
+                0b00 => TestPiece::None,
+                0b01 => TestPiece::First,
+                0b10 => TestPiece::Second,
+                0b11 => TestPiece::Both,
+                _ => panic!("impossible")
+            }
+        }
+    }
+
+    impl Piece for TestPiece {
+        type MatchType = TestMatchType;
+        const AIR: Self = Self::None;
+    }
+
+    #[test]
+    fn get_piece_in_bounds_returns_piece() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn matches_no_patterns_none() {
+        let mut board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
+            BoardState::new(),
+            Vec::new()
+        );
+
+        board.set_piece(Pos::new(1, 2), TestPiece::First);
+        board.set_piece(Pos::new(2, 3), TestPiece::First);
+        assert!(board.matches().is_empty());
+    }
+
+    #[test]
+    fn matches_checks_initial_board() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+
+        board.set_piece(Pos::new(0, 1), TestPiece::First);
+        board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        board.set_piece(Pos::new(4, 6), TestPiece::First);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(
+            board,
+            vec![&pattern]
+        );
+
+        let next_match = &match_board.matches()[0];
+        assert_eq!(Pos::new(0, 1), next_match.changed_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(4, 6)));
+    }
+
+    #[test]
+    fn matches_swap_pieces_match_found_at_first() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        board.set_piece(Pos::new(8, 8), TestPiece::Second);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.swap(Pos::new(6, 6), Pos::new(8, 8));
+
+        let next_match = &match_board.matches()[0];
+        assert_eq!(Pos::new(6, 6), next_match.changed_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(6, 6)));
+    }
+
+    #[test]
+    fn matches_swap_pieces_match_found_at_second() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        board.set_piece(Pos::new(8, 8), TestPiece::Second);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.swap(Pos::new(8, 8), Pos::new(6, 6));
+
+        let next_match = &match_board.matches()[0];
+        assert_eq!(Pos::new(6, 6), next_match.changed_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(6, 6)));
+    }
+
+    #[test]
+    fn matches_swap_self_has_previous_match() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(6, 5), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Both);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.swap(Pos::new(6, 5), Pos::new(6, 5));
+
+        let next_match = &match_board.matches()[0];
+        assert_eq!(Pos::new(0, 0), next_match.changed_pos());
+        assert!(next_match.contains(Pos::new(0, 0)));
+        assert!(next_match.contains(Pos::new(1, 0)));
+        assert!(next_match.contains(Pos::new(6, 5)));
+    }
+
+    #[test]
+    fn matches_wrong_match_type_none_found() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        match_board.set_piece(Pos::new(4, 6), TestPiece::First);
+
+        assert!(match_board.matches().is_empty())
+    }
+
+    #[test]
+    fn matches_matches_when_not_all_in_queue() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        board.set_piece(Pos::new(1, 1), TestPiece::Both);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+
+        let next_match = &match_board.matches()[0];
+        assert_eq!(Pos::new(4, 6), next_match.changed_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(4, 6)));
+    }
+
+    #[test]
+    fn matches_matches_when_changed_twice() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Both);
+
+        let next_match = &match_board.matches()[0];
+
+        assert_eq!(Pos::new(0, 1), next_match.changed_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(4, 6)));
+    }
+
+    #[test]
+    fn matches_never_matches_when_match_overwritten() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+        match_board.set_piece(Pos::new(0, 1), TestPiece::First);
+
+        assert!(match_board.matches().is_empty());
+    }
+
+    #[test]
+    fn matches_set_pieces_matches_earlier_pattern() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos1 = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern_pos2 = vec![
+            Pos::new(2, 3), Pos::new(3, 3),
+            Pos::new(6, 8), Pos::new(7, 8)
+        ];
+        let pattern1 = MatchPattern::new(TestMatchType::Second, &pattern_pos1[..]);
+        let pattern2 = MatchPattern::new(TestMatchType::Second, &pattern_pos2[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern1, &pattern2]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+        match_board.set_piece(Pos::new(5, 6), TestPiece::Both);
+
+        let matches = match_board.matches();
+
+        let next_match = &matches[0];
+        assert_eq!(Pos::new(4, 6), next_match.changed_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(4, 6)));
+        assert!(!next_match.contains(Pos::new(5, 6)));
+
+        let next_next_match = &matches[1];
+        assert_eq!(Pos::new(5, 6), next_next_match.changed_pos());
+        assert!(next_next_match.contains(Pos::new(0, 1)));
+        assert!(next_next_match.contains(Pos::new(1, 1)));
+        assert!(next_next_match.contains(Pos::new(4, 6)));
+        assert!(next_next_match.contains(Pos::new(5, 6)));
+    }
+
+    #[test]
+    fn match_moves_no_patterns_none() {
+        let mut board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
+            BoardState::new(),
+            Vec::new()
+        );
+
+        board.set_piece(Pos::new(1, 2), TestPiece::First);
+        board.set_piece(Pos::new(2, 3), TestPiece::First);
+        assert!(board.match_moves().is_empty());
+    }
+
+    #[test]
+    fn match_moves_checks_initial_board() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+
+        board.set_piece(Pos::new(0, 1), TestPiece::First);
+        board.set_piece(Pos::new(2, 1), TestPiece::Both);
+        board.set_piece(Pos::new(4, 6), TestPiece::First);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(
+            board,
+            vec![&pattern]
+        );
+
+        let next_match = &match_board.match_moves()[0];
+        assert_eq!(Pos::new(1, 1), next_match.missing_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(!next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(4, 6)));
+        assert_eq!(Pos::new(2, 1), next_match.completing_pos());
+    }
+
+    #[test]
+    fn match_moves_swap_pieces_match_found_at_first() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        board.set_piece(Pos::new(6, 7), TestPiece::Both);
+        board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        board.set_piece(Pos::new(6, 6), TestPiece::Second);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.swap(Pos::new(6, 6), Pos::new(8, 8));
+
+        let next_match = &match_board.match_moves()[0];
+        assert_eq!(Pos::new(6, 6), next_match.missing_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(!next_match.contains(Pos::new(6, 6)));
+        assert_eq!(Pos::new(6, 7), next_match.completing_pos());
+    }
+
+    #[test]
+    fn match_moves_swap_pieces_match_found_at_second() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        board.set_piece(Pos::new(6, 6), TestPiece::Second);
+        board.set_piece(Pos::new(6, 7), TestPiece::Both);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.swap(Pos::new(8, 8), Pos::new(6, 6));
+
+        let next_match = &match_board.match_moves()[0];
+        assert_eq!(Pos::new(6, 6), next_match.missing_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(!next_match.contains(Pos::new(6, 6)));
+        assert_eq!(Pos::new(6, 7), next_match.completing_pos());
+    }
+
+    #[test]
+    fn match_moves_swap_self_no_match() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        board.set_piece(Pos::new(6, 6), TestPiece::Second);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.swap(Pos::new(6, 6), Pos::new(6, 6));
+
+        assert!(match_board.match_moves().is_empty())
+    }
+
+    #[test]
+    fn match_moves_wrong_match_type_none_found() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(4, 6), TestPiece::First);
+
+        assert!(match_board.match_moves().is_empty())
+    }
+
+    #[test]
+    fn match_moves_matches_when_not_all_in_queue() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+
+        let next_match = &match_board.match_moves()[0];
+        assert_eq!(Pos::new(1, 1), next_match.missing_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(!next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(4, 6)));
+        assert_eq!(Pos::new(2, 1), next_match.completing_pos());
+    }
+
+    #[test]
+    fn match_moves_matches_when_changed_twice() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(2, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Both);
+
+        let next_match = &match_board.match_moves()[0];
+        assert_eq!(Pos::new(1, 1), next_match.missing_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(!next_match.contains(Pos::new(1, 1)));
+        assert!(next_match.contains(Pos::new(4, 6)));
+        assert_eq!(Pos::new(2, 1), next_match.completing_pos());
+    }
+
+    #[test]
+    fn match_moves_never_matches_when_match_overwritten() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        match_board.set_piece(Pos::new(0, 1), TestPiece::First);
+
+        assert!(match_board.match_moves().is_empty());
+    }
+
+    #[test]
+    fn match_moves_set_pieces_matches_earlier_pattern() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos1 = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern_pos2 = vec![
+            Pos::new(2, 3), Pos::new(3, 3),
+            Pos::new(6, 8), Pos::new(6, 9)
+        ];
+        let pattern1 = MatchPattern::new(TestMatchType::Second, &pattern_pos1[..]);
+        let pattern2 = MatchPattern::new(TestMatchType::Second, &pattern_pos2[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern1, &pattern2]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        match_board.set_piece(Pos::new(4, 7), TestPiece::Both);
+
+        let next_match = &match_board.match_moves()[0];
+        assert_eq!(Pos::new(4, 6), next_match.missing_pos());
+        assert!(next_match.contains(Pos::new(0, 1)));
+        assert!(next_match.contains(Pos::new(1, 1)));
+        assert!(!next_match.contains(Pos::new(4, 6)));
+        assert!(!next_match.contains(Pos::new(4, 7)));
+    }
+
+    #[test]
+    fn pattern_placements_square_fits_everywhere_but_edges() {
+        let match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
+            BoardState::new(),
+            Vec::new()
+        );
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(0, 1), Pos::new(1, 1)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
+        let placements = match_board.pattern_placements(&pattern);
+
+        assert_eq!(14 * 15, placements.len());
+        assert!(placements.contains(&HashSet::from([
+            Pos::new(13, 14), Pos::new(14, 14), Pos::new(13, 15), Pos::new(14, 15)
+        ])));
+    }
+
+    #[test]
+    fn pattern_placements_full_height_one_per_column() {
+        let match_board: MatchBoard<TestMatchType, TestPiece, 3, 3> = MatchBoard::new(
+            BoardState::new(),
+            Vec::new()
+        );
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
+        assert_eq!(3, match_board.pattern_placements(&pattern).len());
+    }
+
+    #[test]
+    fn is_settled_no_matches_or_floating_true() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 1), TestPiece::First);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        assert!(match_board.is_settled());
+    }
+
+    #[test]
+    fn is_settled_pending_match_false() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        match_board.set_piece(Pos::new(2, 0), TestPiece::Both);
+
+        assert!(!match_board.is_settled());
+    }
+
+    #[test]
+    fn is_settled_floating_piece_false() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        match_board.set_piece(Pos::new(4, 5), TestPiece::First);
+
+        assert!(!match_board.is_settled());
+    }
+
+    #[test]
+    fn classic_matches_no_runs_none() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+
+        let match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(board, Vec::new());
+
+        assert!(match_board.classic_matches(3).is_empty());
+    }
+
+    #[test]
+    fn classic_matches_l_shape_merged() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(4, 2), TestPiece::First);
+        board.set_piece(Pos::new(5, 2), TestPiece::First);
+        board.set_piece(Pos::new(6, 2), TestPiece::First);
+        board.set_piece(Pos::new(4, 3), TestPiece::First);
+        board.set_piece(Pos::new(4, 4), TestPiece::First);
+
+        let match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(board, Vec::new());
+
+        let matches = match_board.classic_matches(3);
+        assert_eq!(1, matches.len());
+
+        let expected = HashSet::from([
+            Pos::new(4, 2), Pos::new(5, 2), Pos::new(6, 2),
+            Pos::new(4, 3), Pos::new(4, 4)
+        ]);
+        assert_eq!(expected, matches[0]);
+    }
+
+    #[test]
+    fn classic_matches_separate_runs_not_merged() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 0), TestPiece::Second);
+        board.set_piece(Pos::new(3, 1), TestPiece::Second);
+        board.set_piece(Pos::new(3, 2), TestPiece::Second);
+
+        let match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(board, Vec::new());
+
+        assert_eq!(2, match_board.classic_matches(3).len());
+    }
+
+    #[test]
+    fn classic_matches_barrier_breaks_run() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(14, 13), TestPiece::Second);
+        board.set_piece(Pos::new(14, 14), TestPiece::Second);
+        board.set_piece(Pos::new(14, 15), TestPiece::Second);
+        board.set_barrier_between(Pos::new(14, 14), Pos::new(14, 15), true).unwrap();
+
+        let match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(board, Vec::new());
+
+        assert!(match_board.classic_matches(3).is_empty());
+        assert_eq!(1, match_board.classic_matches(2).len());
+    }
+
+    #[test]
+    fn remove_matches_clears_matched_pieces() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Both);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        let cleared = match_board.remove_matches();
+
+        assert_eq!(HashSet::from([Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)]), cleared);
+        assert!(match_board.matches().is_empty());
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(0, 0)));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(1, 0)));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(2, 0)));
+        assert_eq!(TestPiece::First, match_board.piece(Pos::new(3, 0)));
+    }
+
+    #[test]
+    fn clear_preview_no_matches_empty() {
+        let match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
+            BoardState::new(),
+            Vec::new()
+        );
+
+        assert!(match_board.clear_preview().is_empty());
+    }
+
+    #[test]
+    fn clear_preview_equals_removed_positions() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        board.set_piece(Pos::new(5, 1), TestPiece::Second);
+        board.set_piece(Pos::new(5, 2), TestPiece::Second);
+        board.set_piece(Pos::new(5, 3), TestPiece::Second);
+
+        let horizontal_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let horizontal = MatchPattern::new(TestMatchType::Second, &horizontal_pos[..]);
+        let vertical_pos = [Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)];
+        let vertical = MatchPattern::new(TestMatchType::Second, &vertical_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&horizontal, &vertical]);
+
+        let preview = match_board.clear_preview();
+        assert_eq!(6, preview.len());
+        assert_eq!(TestPiece::Second, match_board.piece(Pos::new(5, 3)));
+
+        assert_eq!(preview, match_board.remove_matches());
+    }
+
+    #[test]
+    fn take_log_disabled_empty() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let mut match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(board, Vec::new());
+        match_board.set_piece(Pos::new(0, 0), TestPiece::First);
+        match_board.swap(Pos::new(0, 0), Pos::new(0, 1));
+
+        assert!(match_board.take_log().is_empty());
+    }
+
+    #[test]
+    fn take_log_enabled_records_in_order() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::with_log(board, vec![&pattern], true);
+        match_board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        match_board.set_piece(Pos::new(1, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        match_board.swap(Pos::new(1, 1), Pos::new(1, 0));
+        match_board.remove_matches();
+
+        assert_eq!(vec![
+            BoardEvent::SetPiece(Pos::new(0, 0), TestPiece::Second),
+            BoardEvent::SetPiece(Pos::new(1, 1), TestPiece::Second),
+            BoardEvent::SetPiece(Pos::new(2, 0), TestPiece::Second),
+            BoardEvent::Swap(Pos::new(1, 1), Pos::new(1, 0)),
+            BoardEvent::RemoveMatches(vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)])
+        ], match_board.take_log());
+
+        assert!(match_board.take_log().is_empty());
+    }
+
+    #[test]
+    fn end_game_returns_board() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+
+        match_board.set_piece(Pos::new(0, 1), TestPiece::First);
+        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+
+        match_board.swap(Pos::new(4, 6), Pos::new(5, 6));
+
+        let final_board = match_board.end_game();
+
+        for x in 0..15 {
+            for y in 0..16 {
+                if x == 0 && y == 1 {
+                    assert_eq!(TestPiece::First, final_board.piece(Pos::new(x, y)));
+                } else if x == 1 && y == 1 {
+                    assert_eq!(TestPiece::Both, final_board.piece(Pos::new(x, y)));
+                } else if x == 5 && y == 6 {
+                    assert_eq!(TestPiece::Second, final_board.piece(Pos::new(x, y)));
+                } else {
+                    assert_eq!(TestPiece::None, final_board.piece(Pos::new(x, y)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_by_pattern_two_patterns_two_groups() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        board.set_piece(Pos::new(5, 1), TestPiece::Second);
+        board.set_piece(Pos::new(5, 2), TestPiece::Second);
+        board.set_piece(Pos::new(5, 3), TestPiece::Second);
+
+        let horizontal_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let horizontal = MatchPattern::new(TestMatchType::Second, &horizontal_pos[..]);
+        let vertical_pos = [Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)];
+        let vertical = MatchPattern::new(TestMatchType::Second, &vertical_pos[..]);
+        let unused_pos = [Pos::new(0, 0), Pos::new(1, 1)];
+        let unused = MatchPattern::new(TestMatchType::First, &unused_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&horizontal, &unused, &vertical]);
+        let groups = match_board.matches_by_pattern();
+
+        assert_eq!(2, groups.len());
+        assert!(!groups.contains_key(&1));
+
+        assert_eq!(1, groups[&0].len());
+        assert!(groups[&0][0].contains(Pos::new(1, 0)));
+        assert_eq!(1, groups[&2].len());
+        assert!(groups[&2][0].contains(Pos::new(5, 2)));
+    }
+
+    #[test]
+    fn match_moves_cached_equal_full_scan_after_edits() {
+        let board = BoardState::<TestPiece, 15, 16>::new();
+
+        let horizontal_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let horizontal = MatchPattern::new(TestMatchType::First, &horizontal_pos[..]);
+        let l_pos = [Pos::new(0, 0), Pos::new(0, 1), Pos::new(1, 0), Pos::new(2, 0)];
+        let l_shape = MatchPattern::new(TestMatchType::Second, &l_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&l_shape, &horizontal]);
+        match_board.set_piece(Pos::new(0, 0), TestPiece::First);
+        match_board.set_piece(Pos::new(1, 0), TestPiece::First);
+        match_board.set_piece(Pos::new(2, 1), TestPiece::First);
+        match_board.set_piece(Pos::new(6, 5), TestPiece::Second);
+        match_board.set_piece(Pos::new(6, 6), TestPiece::Second);
+        match_board.set_piece(Pos::new(8, 5), TestPiece::Both);
+        match_board.set_piece(Pos::new(9, 6), TestPiece::Second);
+        match_board.swap(Pos::new(2, 1), Pos::new(3, 0));
+        match_board.set_piece(Pos::new(14, 15), TestPiece::First);
+        match_board.set_piece(Pos::new(13, 15), TestPiece::First);
+        match_board.set_piece(Pos::new(12, 14), TestPiece::First);
+        match_board.set_piece(Pos::new(6, 6), TestPiece::First);
+        match_board.swap(Pos::new(9, 6), Pos::new(9, 7));
+
+        let full_scan_board = MatchBoard::new(match_board.clone().end_game(), vec![&l_shape, &horizontal]);
+
+        assert!(!match_board.match_moves().is_empty());
+        assert_eq!(full_scan_board.match_moves(), match_board.match_moves());
+    }
+
+    #[test]
+    fn remove_matches_counted_counts_by_match_type() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        board.set_piece(Pos::new(5, 1), TestPiece::First);
+        board.set_piece(Pos::new(5, 2), TestPiece::First);
+
+        let three_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let three = MatchPattern::new(TestMatchType::Second, &three_pos[..]);
+        let two_pos = [Pos::new(0, 0), Pos::new(0, 1)];
+        let two = MatchPattern::new(TestMatchType::First, &two_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&three, &two]);
+        let counts = match_board.remove_matches_counted();
+
+        assert_eq!(2, counts.len());
+        assert_eq!(3, counts[&TestMatchType::Second]);
+        assert_eq!(2, counts[&TestMatchType::First]);
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(1, 0)));
+        assert!(match_board.matches().is_empty());
+    }
+
+    #[test]
+    fn remove_matches_counted_no_matches_empty() {
+        let mut match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
+            BoardState::new(),
+            Vec::new()
+        );
+
+        assert!(match_board.remove_matches_counted().is_empty());
+    }
+
+    #[test]
+    fn has_move_within_two_swaps_needed() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(4, 0), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        assert!(!match_board.has_move_within(0));
+        assert!(!match_board.has_move_within(1));
+        assert!(match_board.has_move_within(2));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(2, 0)));
+    }
+
+    #[test]
+    fn has_move_within_barrier_blocks_swap() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(3, 0), TestPiece::Second);
+        board.set_barrier_between(Pos::new(2, 0), Pos::new(3, 0), true).unwrap();
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        assert!(!match_board.has_move_within(1));
+    }
+
+    #[test]
+    fn has_move_within_one_swap_matches_match_moves() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(3, 0), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        assert!(!match_board.match_moves().is_empty());
+        assert!(!match_board.has_move_within(0));
+        assert!(match_board.has_move_within(1));
+    }
+
+    #[test]
+    fn has_move_within_one_swap_run_pattern() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
+
+        let run_pattern = RunPattern::new(TestMatchType::First, Orientation::Horizontal, 3);
+        let match_board = MatchBoard::with_run_patterns(board, Vec::new(), vec![&run_pattern]);
+
+        assert!(match_board.match_moves().is_empty());
+        assert!(!match_board.has_move_within(0));
+        assert!(match_board.has_move_within(1));
+    }
+
+    #[test]
+    fn has_move_within_require_both_occupied_skips_air_swaps() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(3, 0), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        assert!(match_board.has_move_within(1));
+
+        match_board.set_require_both_occupied(true);
+        assert!(!match_board.has_move_within(2));
+    }
+
+    #[test]
+    fn has_move_within_existing_match_zero_depth() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        assert!(match_board.has_move_within(0));
+    }
+
+    #[test]
+    fn set_inert_cell_in_run_prevents_match() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        assert_eq!(1, match_board.matches().len());
+
+        match_board.set_inert([Pos::new(1, 0)], true);
+        assert!(match_board.is_inert(Pos::new(1, 0)));
+        assert!(match_board.matches().is_empty());
+
+        match_board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        assert!(match_board.matches().is_empty());
+
+        match_board.set_inert([Pos::new(1, 0)], false);
+        assert_eq!(1, match_board.matches().len());
+    }
+
+    #[test]
+    fn set_inert_completing_pos_no_match_move() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        assert_eq!(1, match_board.match_moves().len());
+
+        match_board.set_inert([Pos::new(2, 1)], true);
+        assert!(match_board.match_moves().is_empty());
+    }
+
+    #[test]
+    fn with_swap_observes_swap_then_reverts() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::with_log(board, vec![&pattern], true);
+        let start_board = match_board.clone();
+
+        let match_count = match_board.with_swap(Pos::new(2, 1), Pos::new(2, 0), |swapped| {
+            assert_eq!(TestPiece::Second, swapped.piece(Pos::new(2, 0)));
+            assert_eq!(TestPiece::None, swapped.piece(Pos::new(2, 1)));
+            swapped.matches().len()
+        });
+
+        assert_eq!(1, match_count);
+        assert_eq!(start_board, match_board);
+        assert!(match_board.take_log().is_empty());
+    }
+
+    #[test]
+    fn with_swap_panic_reverts() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+
+        let mut match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(board, Vec::new());
+        let start_board = match_board.clone();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            match_board.with_swap(Pos::new(0, 0), Pos::new(0, 1), |_| panic!("test panic"))
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(start_board, match_board);
+    }
+
+    #[test]
+    fn match_breaking_swaps_member_swapped_out() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
+        board.set_piece(Pos::new(0, 1), TestPiece::Both);
+        board.set_barrier_between(Pos::new(2, 0), Pos::new(2, 1), true).unwrap();
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+        let breaking_swaps: HashSet<(Pos<15, 16>, Pos<15, 16>)> = match_board.match_breaking_swaps()
+            .into_iter()
+            .collect();
+
+        assert_eq!(HashSet::from([
+            (Pos::new(1, 0), Pos::new(1, 1)),
+            (Pos::new(2, 0), Pos::new(3, 0))
+        ]), breaking_swaps);
     }
 
-    impl BitAnd for TestPiece {
-        type Output = Self;
+    #[test]
+    fn match_breaking_swaps_no_matches_empty() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
 
-        fn bitand(self, rhs: Self) -> Self::Output {
-            match self as u8 & rhs as u8 {
-                0b00 => TestPiece::None,
-                0b01 => TestPiece::First,
-                0b10 => TestPiece::Second,
-                0b11 => TestPiece::Both,
-                _ => panic!("impossible")
-            }
-        }
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        assert!(match_board.match_breaking_swaps().is_empty());
     }
 
-    impl Piece for TestPiece {
-        type MatchType = TestMatchType;
-        const AIR: Self = Self::None;
+    #[test]
+    fn set_pattern_enabled_disabled_matches_removed_on_rescan() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        board.set_piece(Pos::new(5, 1), TestPiece::Second);
+        board.set_piece(Pos::new(5, 2), TestPiece::Second);
+        board.set_piece(Pos::new(5, 3), TestPiece::Second);
+
+        let horizontal_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let horizontal = MatchPattern::new(TestMatchType::Second, &horizontal_pos[..]);
+        let vertical_pos = [Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)];
+        let vertical = MatchPattern::new(TestMatchType::Second, &vertical_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&horizontal, &vertical]);
+        assert_eq!(2, match_board.matches().len());
+
+        match_board.set_pattern_enabled(1, false);
+        assert_eq!(2, match_board.matches().len());
+
+        match_board.rescan_all();
+        assert_eq!(1, match_board.matches().len());
+        assert!(match_board.matches()[0].contains(Pos::new(1, 0)));
+
+        match_board.set_pattern_enabled(1, true);
+        match_board.rescan_all();
+        assert_eq!(2, match_board.matches().len());
     }
 
     #[test]
-    fn get_piece_in_bounds_returns_piece() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
+    fn set_pattern_enabled_disabled_no_match_moves() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
-        let match_board = MatchBoard::new(board, vec![&pattern]);
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        assert_eq!(1, match_board.match_moves().len());
 
-        assert_eq!(TestPiece::None, match_board.piece(Pos::new(0, 0)));
+        match_board.set_pattern_enabled(0, false);
+        match_board.rescan_all();
+        assert!(match_board.match_moves().is_empty());
     }
 
     #[test]
-    fn matches_no_patterns_none() {
-        let mut board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
+    #[should_panic]
+    fn set_pattern_enabled_out_of_bounds_panics() {
+        let mut match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
             BoardState::new(),
             Vec::new()
         );
 
-        board.set_piece(Pos::new(1, 2), TestPiece::First);
-        board.set_piece(Pos::new(2, 3), TestPiece::First);
-        assert!(board.matches().is_empty());
+        match_board.set_pattern_enabled(0, false);
     }
 
     #[test]
-    fn matches_checks_initial_board() {
+    fn clear_positions_five_cells_cleared_once() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        board.set_piece(Pos::new(5, 5), TestPiece::First);
+        board.set_piece(Pos::new(9, 2), TestPiece::Both);
 
-        board.set_piece(Pos::new(0, 1), TestPiece::First);
-        board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        board.set_piece(Pos::new(4, 6), TestPiece::First);
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        assert_eq!(1, match_board.matches().len());
 
-        let match_board = MatchBoard::new(
-            board,
-            vec![&pattern]
+        let cleared = match_board.clear_positions([
+            Pos::new(1, 0), Pos::new(5, 5), Pos::new(9, 2), Pos::new(14, 15), Pos::new(7, 7), Pos::new(5, 5)
+        ]);
+
+        assert_eq!(3, cleared);
+        assert!(match_board.matches().is_empty());
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(9, 2)));
+        assert_eq!(TestPiece::Second, match_board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn clear_positions_none_board_unchanged() {
+        let mut match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
+            BoardState::new(),
+            Vec::new()
         );
+        let start_board = match_board.clone();
 
-        let next_match = &match_board.matches()[0];
-        assert_eq!(Pos::new(0, 1), next_match.changed_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(4, 6)));
+        assert_eq!(0, match_board.clear_positions([]));
+        assert_eq!(start_board, match_board);
     }
 
     #[test]
-    fn matches_swap_pieces_match_found_at_first() {
+    fn pattern_index_second_pattern_index_one() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(5, 1), TestPiece::Second);
+        board.set_piece(Pos::new(5, 2), TestPiece::Second);
+        board.set_piece(Pos::new(5, 3), TestPiece::Second);
+
+        let horizontal_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let horizontal = MatchPattern::new(TestMatchType::Second, &horizontal_pos[..]);
+        let vertical_pos = [Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)];
+        let vertical = MatchPattern::new(TestMatchType::Second, &vertical_pos[..]);
+
+        let mut match_board = MatchBoard::new(board, vec![&horizontal, &vertical]);
+        assert_eq!(1, match_board.matches().len());
+        assert_eq!(1, match_board.matches()[0].pattern_index());
+
+        match_board.set_piece(Pos::new(6, 1), TestPiece::Second);
+        match_board.set_piece(Pos::new(7, 1), TestPiece::Second);
+        assert!(match_board.matches().iter().any(|current_match| current_match.pattern_index() == 0));
+    }
 
-        board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        board.set_piece(Pos::new(8, 8), TestPiece::Second);
+    #[test]
+    fn preview_set_completing_piece_returns_match() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
-        let mut match_board = MatchBoard::new(board, vec![&pattern]);
-
-        match_board.swap(Pos::new(6, 6), Pos::new(8, 8));
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+        let preview = match_board.preview_set(Pos::new(1, 0), TestPiece::Both);
+
+        assert_eq!(1, preview.len());
+        assert_eq!(pattern, *preview[0].pattern());
+        assert_eq!(Pos::new(1, 0), preview[0].changed_pos());
+        assert_eq!(
+            HashSet::from([Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)]),
+            preview[0].iter().copied().collect::<HashSet<_>>()
+        );
 
-        let next_match = &match_board.matches()[0];
-        assert_eq!(Pos::new(6, 6), next_match.changed_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(6, 6)));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(1, 0)));
+        assert!(match_board.matches().is_empty());
     }
 
     #[test]
-    fn matches_swap_pieces_match_found_at_second() {
+    fn preview_set_wrong_piece_no_matches() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
 
-        board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        assert!(match_board.preview_set(Pos::new(1, 0), TestPiece::First).is_empty());
+    }
+
+    #[test]
+    fn matches_in_region_inside_and_outside() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
         board.set_piece(Pos::new(8, 8), TestPiece::Second);
+        board.set_piece(Pos::new(9, 8), TestPiece::Second);
+        board.set_piece(Pos::new(10, 8), TestPiece::Second);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
-        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+        assert_eq!(2, match_board.matches().len());
 
-        match_board.swap(Pos::new(8, 8), Pos::new(6, 6));
+        let in_region = match_board.matches_in_region(Pos::new(10, 12), Pos::new(14, 5));
+        assert_eq!(1, in_region.len());
+        assert!(in_region[0].contains(Pos::new(9, 8)));
 
-        let next_match = &match_board.matches()[0];
-        assert_eq!(Pos::new(6, 6), next_match.changed_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(6, 6)));
+        assert_eq!(2, match_board.matches_in_region(Pos::new(0, 15), Pos::new(14, 0)).len());
+        assert!(match_board.matches_in_region(Pos::new(3, 7), Pos::new(7, 0)).is_empty());
     }
 
     #[test]
-    fn matches_swap_self_has_previous_match() {
+    fn match_moves_barrier_between_completing_and_missing_no_move() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
-
         board.set_piece(Pos::new(0, 0), TestPiece::Second);
-        board.set_piece(Pos::new(6, 5), TestPiece::Second);
-        board.set_piece(Pos::new(1, 0), TestPiece::Both);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+        board.set_barrier_between(Pos::new(2, 0), Pos::new(2, 1), true).unwrap();
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
         let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        assert!(match_board.match_moves().is_empty());
 
-        match_board.swap(Pos::new(6, 5), Pos::new(6, 5));
+        match_board.set_piece(Pos::new(3, 0), TestPiece::Second);
+        let match_moves = match_board.match_moves();
+        assert_eq!(1, match_moves.len());
+        assert_eq!((Pos::new(3, 0), Pos::new(2, 0)), match_moves[0].as_swap());
+    }
 
-        let next_match = &match_board.matches()[0];
-        assert_eq!(Pos::new(0, 0), next_match.changed_pos());
-        assert!(next_match.contains(Pos::new(0, 0)));
-        assert!(next_match.contains(Pos::new(1, 0)));
-        assert!(next_match.contains(Pos::new(6, 5)));
+    #[test]
+    fn try_swap_no_match_error_unchanged() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::First);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::with_log(board, vec![&pattern], true);
+        let start_board = match_board.clone();
+
+        assert_eq!(
+            Err(InvalidSwap { first: Pos::new(2, 1), second: Pos::new(2, 0) }),
+            match_board.try_swap(Pos::new(2, 1), Pos::new(2, 0))
+        );
+        assert_eq!(start_board, match_board);
     }
 
     #[test]
-    fn matches_wrong_match_type_none_found() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
+    fn try_swap_non_adjacent_or_barrier_error() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+        board.set_piece(Pos::new(3, 0), TestPiece::Second);
+        board.set_barrier_between(Pos::new(2, 0), Pos::new(2, 1), true).unwrap();
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
         let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        let start_board = match_board.clone();
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        match_board.set_piece(Pos::new(4, 6), TestPiece::First);
+        assert_eq!(
+            Err(InvalidSwap { first: Pos::new(2, 1), second: Pos::new(2, 0) }),
+            match_board.try_swap(Pos::new(2, 1), Pos::new(2, 0))
+        );
+        assert_eq!(
+            Err(InvalidSwap { first: Pos::new(3, 0), second: Pos::new(1, 0) }),
+            match_board.try_swap(Pos::new(3, 0), Pos::new(1, 0))
+        );
+        assert_eq!(start_board, match_board);
+    }
 
-        assert!(match_board.matches().is_empty())
+    #[test]
+    fn try_swap_creates_match_swapped() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::with_log(board, vec![&pattern], true);
+
+        assert_eq!(Ok(()), match_board.try_swap(Pos::new(2, 1), Pos::new(2, 0)));
+        assert_eq!(TestPiece::Second, match_board.piece(Pos::new(2, 0)));
+        assert_eq!(1, match_board.matches().len());
+        assert_eq!(vec![BoardEvent::Swap(Pos::new(2, 1), Pos::new(2, 0))], match_board.take_log());
     }
 
     #[test]
-    fn matches_matches_when_not_all_in_queue() {
+    fn match_moves_diagonal_completing_pos_only_when_allowed() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
-        board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        board.set_piece(Pos::new(1, 1), TestPiece::Both);
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(3, 1), TestPiece::Second);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
+        let orthogonal_board = MatchBoard::new(board, vec![&pattern]);
+        assert!(orthogonal_board.match_moves().is_empty());
+
+        let mut diagonal_board = MatchBoard::with_options(board, vec![&pattern], Vec::new(), false, true);
+        let match_moves = diagonal_board.match_moves();
+        assert_eq!(1, match_moves.len());
+        assert_eq!(Pos::new(2, 0), match_moves[0].missing_pos());
+        assert_eq!(Pos::new(3, 1), match_moves[0].completing_pos());
+
+        assert_eq!(Ok(()), diagonal_board.try_swap(Pos::new(3, 1), Pos::new(2, 0)));
+        assert_eq!(1, diagonal_board.matches().len());
+    }
+
+    #[test]
+    fn step_clears_falls_and_refills_once() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 1), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
         let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        let result = match_board.step(|_| TestPiece::Second);
 
-        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+        let expected_cleared: HashSet<Pos<15, 16>> = pattern_pos.iter().copied().collect();
+        assert_eq!(expected_cleared, result.cleared);
+        assert!(!result.moves.is_empty());
+        assert_eq!(15 * 16 - 1, result.spawned.len());
+        assert!(result.spawned.iter().all(|&(pos, piece)| pos != Pos::new(1, 0) && piece == TestPiece::Second));
 
-        let next_match = &match_board.matches()[0];
-        assert_eq!(Pos::new(4, 6), next_match.changed_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(4, 6)));
+        assert_eq!(TestPiece::Second, match_board.piece(Pos::new(1, 0)));
+        assert!(match_board.matches().is_empty());
     }
 
     #[test]
-    fn matches_matches_when_changed_twice() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
+    fn step_inert_piece_stays_put() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 2), TestPiece::Second);
+        board.set_piece(Pos::new(1, 3), TestPiece::Second);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
 
         let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        match_board.set_inert([Pos::new(1, 2)], true);
+        let result = match_board.step(|_| TestPiece::Second);
+
+        assert!(result.moves.iter().all(|&(first, second)| first != Pos::new(1, 2) && second != Pos::new(1, 2)));
+        assert_eq!(TestPiece::Second, match_board.piece(Pos::new(1, 2)));
+        assert_eq!(TestPiece::Second, match_board.piece(Pos::new(1, 3)));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(1, 0)));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(1, 1)));
+    }
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Both);
+    #[test]
+    fn step_inert_empty_cell_not_moved_or_refilled() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 0), TestPiece::First);
+        board.set_piece(Pos::new(4, 9), TestPiece::Second);
 
-        let next_match = &match_board.matches()[0];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
 
-        assert_eq!(Pos::new(0, 1), next_match.changed_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(4, 6)));
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        match_board.set_inert([Pos::new(4, 5), Pos::new(1, 15)], true);
+        let result = match_board.step(|_| TestPiece::Second);
+
+        let is_frozen = |pos: Pos<15, 16>| pos == Pos::new(4, 5) || pos == Pos::new(1, 15);
+        assert!(result.moves.iter().all(|&(first, second)| !is_frozen(first) && !is_frozen(second)));
+        assert!(result.spawned.iter().all(|&(pos, _)| !is_frozen(pos)));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(4, 5)));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(1, 15)));
+        assert_eq!(TestPiece::Second, match_board.piece(Pos::new(4, 6)));
+        assert_eq!(TestPiece::None, match_board.piece(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn preview_swap_match_count_two_matches() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::First);
+        board.set_piece(Pos::new(3, 1), TestPiece::Second);
+        board.set_piece(Pos::new(4, 1), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let first_pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+        let second_pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&first_pattern, &second_pattern]);
+        let start_board = match_board.clone();
+
+        assert_eq!(2, match_board.preview_swap_match_count(Pos::new(2, 0), Pos::new(2, 1)));
+        assert_eq!(0, match_board.preview_swap_match_count(Pos::new(0, 0), Pos::new(0, 1)));
+        assert_eq!(start_board, match_board);
+    }
+
+    #[test]
+    fn run_pattern_run_of_five_single_match() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        for x in 2..7 {
+            board.set_piece(Pos::new(x, 4), TestPiece::First);
+        }
+
+        let run_pattern = RunPattern::new(TestMatchType::First, Orientation::Horizontal, 3);
+        let match_board = MatchBoard::with_run_patterns(board, Vec::new(), vec![&run_pattern]);
+
+        let expected: HashSet<Pos<15, 16>> = (2..7).map(|x| Pos::new(x, 4)).collect();
+        assert_eq!(1, match_board.matches().len());
+        assert_eq!(expected, match_board.matches()[0].iter().copied().collect());
+        assert_eq!(5, match_board.matches()[0].pattern().iter().count());
+        assert_eq!(0, match_board.matches()[0].pattern_index());
+    }
+
+    #[test]
+    fn run_pattern_growing_run_replaces_match() {
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+        let run_pattern = RunPattern::new(TestMatchType::First, Orientation::Vertical, 3);
+        let mut match_board = MatchBoard::with_run_patterns(
+            BoardState::<TestPiece, 15, 16>::new(),
+            vec![&pattern],
+            vec![&run_pattern]
+        );
+
+        for y in [0, 1, 2, 4] {
+            match_board.set_piece(Pos::new(3, y), TestPiece::First);
+        }
+        assert_eq!(1, match_board.matches().len());
+        assert_eq!(3, match_board.matches()[0].iter().count());
+        assert_eq!(1, match_board.matches()[0].pattern_index());
+
+        match_board.set_piece(Pos::new(3, 3), TestPiece::First);
+        assert_eq!(1, match_board.matches().len());
+        assert_eq!(5, match_board.matches()[0].iter().count());
+    }
+
+    #[test]
+    fn run_pattern_split_run_keeps_long_part() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        for x in 0..6 {
+            board.set_piece(Pos::new(x, 0), TestPiece::First);
+        }
+
+        let run_pattern = RunPattern::new(TestMatchType::First, Orientation::Horizontal, 3);
+        let mut match_board = MatchBoard::with_run_patterns(board, Vec::new(), vec![&run_pattern]);
+        match_board.set_piece(Pos::new(2, 0), TestPiece::Second);
+
+        let expected: HashSet<Pos<15, 16>> = (3..6).map(|x| Pos::new(x, 0)).collect();
+        assert_eq!(1, match_board.matches().len());
+        assert_eq!(expected, match_board.matches()[0].iter().copied().collect());
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn rescan_all_parallel_equals_serial() {
+        let horizontal_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let horizontal = MatchPattern::new(TestMatchType::First, &horizontal_pos[..]);
+        let l_pos = [Pos::new(0, 0), Pos::new(0, 1), Pos::new(1, 0)];
+        let l_shape = MatchPattern::new(TestMatchType::Second, &l_pos[..]);
+        let run_pattern = RunPattern::new(TestMatchType::Second, Orientation::Vertical, 4);
+
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        for x in 0..15 {
+            for y in 0..16 {
+                let piece = match (x * 7 + y * 3 + x * y) % 5 {
+                    0 | 1 => TestPiece::First,
+                    2 => TestPiece::Second,
+                    3 => TestPiece::Both,
+                    _ => TestPiece::None
+                };
+                board.set_piece(Pos::new(x, y), piece);
+            }
+        }
+
+        let mut serial_board = MatchBoard::with_run_patterns(board, vec![&l_shape, &horizontal], vec![&run_pattern]);
+        let mut parallel_board = serial_board.clone();
+        serial_board.set_inert([Pos::new(4, 4)], true);
+        parallel_board.set_inert([Pos::new(4, 4)], true);
+
+        serial_board.rescan_all();
+        parallel_board.rescan_all_parallel();
+
+        assert!(!serial_board.matches().is_empty());
+        assert!(!serial_board.match_moves().is_empty());
+        assert_eq!(serial_board, parallel_board);
+    }
+
+    #[test]
+    fn special_matches_plus_is_cross_intersection() {
+        let pattern_pos = [Pos::new(1, 0), Pos::new(0, 1), Pos::new(1, 1), Pos::new(2, 1), Pos::new(1, 2)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        for pos in pattern_pos {
+            board.set_piece(Pos::new(pos.x() + 4, pos.y() + 6), TestPiece::First);
+        }
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+        let special_matches = match_board.special_matches();
+
+        assert_eq!(1, special_matches.len());
+        assert_eq!(MatchShape::Cross, special_matches[0].shape);
+        assert!(special_matches[0].shape.is_intersection());
+        assert_eq!(Pos::new(5, 7), special_matches[0].pivot);
+        assert_eq!(5, special_matches[0].positions.len());
     }
 
     #[test]
-    fn matches_never_matches_when_match_overwritten() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
+    fn classify_shape_named_shapes() {
+        type Board<'a> = MatchBoard<'a, TestMatchType, TestPiece, 15, 16>;
+
+        let line = [Pos::new(2, 3), Pos::new(2, 4), Pos::new(2, 5)];
+        let l_shape = [Pos::new(2, 3), Pos::new(3, 3), Pos::new(4, 3), Pos::new(2, 4), Pos::new(2, 5)];
+        let t_shape = [Pos::new(2, 5), Pos::new(3, 5), Pos::new(4, 5), Pos::new(3, 4), Pos::new(3, 3)];
+        let square = [Pos::new(2, 3), Pos::new(3, 3), Pos::new(2, 4), Pos::new(3, 4)];
+        let diagonal = [Pos::new(2, 3), Pos::new(3, 4), Pos::new(4, 5)];
+
+        assert_eq!(Some((MatchShape::Line, None)), Board::classify_shape(&line));
+        assert_eq!(Some((MatchShape::L, Some(Pos::new(2, 3)))), Board::classify_shape(&l_shape));
+        assert_eq!(Some((MatchShape::T, Some(Pos::new(3, 5)))), Board::classify_shape(&t_shape));
+        assert_eq!(Some((MatchShape::Square, None)), Board::classify_shape(&square));
+        assert_eq!(None, Board::classify_shape(&diagonal));
+    }
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+    #[test]
+    fn matches_involving_only_touching_matches() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), TestPiece::First);
+            board.set_piece(Pos::new(x, 5), TestPiece::First);
+        }
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
 
         let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        let spawned = HashSet::from([Pos::new(0, 6), Pos::new(1, 6), Pos::new(2, 6)]);
+        for &pos in spawned.iter() {
+            match_board.set_piece(pos, TestPiece::First);
+        }
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
-        match_board.set_piece(Pos::new(0, 1), TestPiece::First);
+        assert_eq!(3, match_board.matches().len());
 
-        assert!(match_board.matches().is_empty());
+        let involving = match_board.matches_involving(&spawned);
+        assert_eq!(1, involving.len());
+        assert!(involving[0].contains(Pos::new(1, 6)));
+        assert!(match_board.matches_involving(&HashSet::new()).is_empty());
     }
 
     #[test]
-    fn matches_set_pieces_matches_earlier_pattern() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
-
-        let pattern_pos1 = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern_pos2 = vec![
-            Pos::new(2, 3), Pos::new(3, 3),
-            Pos::new(6, 8), Pos::new(7, 8)
-        ];
-        let pattern1 = MatchPattern::new(TestMatchType::Second, &pattern_pos1[..]);
-        let pattern2 = MatchPattern::new(TestMatchType::Second, &pattern_pos2[..]);
-
-        let mut match_board = MatchBoard::new(board, vec![&pattern1, &pattern2]);
+    fn try_swap_air_endpoint_rejected_when_both_required() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 1), TestPiece::Second);
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
-        match_board.set_piece(Pos::new(5, 6), TestPiece::Both);
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
-        let matches = match_board.matches();
+        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        let mut allowing_board = match_board.clone();
+        match_board.set_require_both_occupied(true);
+        let start_board = match_board.clone();
 
-        let next_match = &matches[0];
-        assert_eq!(Pos::new(4, 6), next_match.changed_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(4, 6)));
-        assert!(!next_match.contains(Pos::new(5, 6)));
+        assert_eq!(
+            Err(InvalidSwap { first: Pos::new(2, 1), second: Pos::new(2, 0) }),
+            match_board.try_swap(Pos::new(2, 1), Pos::new(2, 0))
+        );
+        assert_eq!(start_board, match_board);
 
-        let next_next_match = &matches[1];
-        assert_eq!(Pos::new(5, 6), next_next_match.changed_pos());
-        assert!(next_next_match.contains(Pos::new(0, 1)));
-        assert!(next_next_match.contains(Pos::new(1, 1)));
-        assert!(next_next_match.contains(Pos::new(4, 6)));
-        assert!(next_next_match.contains(Pos::new(5, 6)));
+        assert_eq!(Ok(()), allowing_board.try_swap(Pos::new(2, 1), Pos::new(2, 0)));
     }
 
     #[test]
-    fn match_moves_no_patterns_none() {
-        let mut board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
-            BoardState::new(),
-            Vec::new()
-        );
+    fn map_in_place_updates_matches_and_logs_changes() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 2), TestPiece::First);
+        }
 
-        board.set_piece(Pos::new(1, 2), TestPiece::First);
-        board.set_piece(Pos::new(2, 3), TestPiece::First);
-        assert!(board.match_moves().is_empty());
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+
+        let mut match_board = MatchBoard::with_log(board, vec![&pattern], true);
+        assert!(match_board.matches().is_empty());
+
+        match_board.map_in_place(|_, piece| match piece {
+            TestPiece::First => TestPiece::Second,
+            other => other
+        });
+
+        assert_eq!(1, match_board.matches().len());
+        assert_eq!(1, match_board.last_matches().len());
+        assert_eq!(3, match_board.take_log().len());
     }
 
     #[test]
-    fn match_moves_checks_initial_board() {
+    fn move_count_two_independent_moves() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
 
-        board.set_piece(Pos::new(0, 1), TestPiece::First);
-        board.set_piece(Pos::new(2, 1), TestPiece::Both);
-        board.set_piece(Pos::new(4, 6), TestPiece::First);
+        board.set_piece(Pos::new(0, 5), TestPiece::First);
+        board.set_piece(Pos::new(1, 5), TestPiece::First);
+        board.set_piece(Pos::new(3, 5), TestPiece::First);
+        board.set_piece(Pos::new(2, 6), TestPiece::First);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        board.set_piece(Pos::new(10, 10), TestPiece::First);
+        board.set_piece(Pos::new(11, 10), TestPiece::First);
+        board.set_piece(Pos::new(12, 11), TestPiece::First);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
 
-        let match_board = MatchBoard::new(
-            board,
-            vec![&pattern]
-        );
+        let match_board = MatchBoard::new(board, vec![&pattern]);
 
-        let next_match = &match_board.match_moves()[0];
-        assert_eq!(Pos::new(1, 1), next_match.missing_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(!next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(4, 6)));
-        assert_eq!(Pos::new(2, 1), next_match.completing_pos());
+        assert_eq!(2, match_board.move_count());
     }
 
     #[test]
-    fn match_moves_swap_pieces_match_found_at_first() {
+    fn placement_useful_match_true_isolated_false() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
 
-        board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        board.set_piece(Pos::new(6, 7), TestPiece::Both);
-        board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        board.set_piece(Pos::new(6, 6), TestPiece::Second);
-
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
-        let mut match_board = MatchBoard::new(board, vec![&pattern]);
-
-        match_board.swap(Pos::new(6, 6), Pos::new(8, 8));
+        let match_board = MatchBoard::new(board, vec![&pattern]);
 
-        let next_match = &match_board.match_moves()[0];
-        assert_eq!(Pos::new(6, 6), next_match.missing_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(!next_match.contains(Pos::new(6, 6)));
-        assert_eq!(Pos::new(6, 7), next_match.completing_pos());
+        assert!(match_board.placement_useful(Pos::new(1, 0), TestPiece::Second));
+        assert!(!match_board.placement_useful(Pos::new(10, 10), TestPiece::Second));
+        assert!(match_board.matches().is_empty());
     }
 
     #[test]
-    fn match_moves_swap_pieces_match_found_at_second() {
+    fn placement_useful_completing_piece_true() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
 
-        board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        board.set_piece(Pos::new(6, 6), TestPiece::Second);
-        board.set_piece(Pos::new(6, 7), TestPiece::Both);
-
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
-        let mut match_board = MatchBoard::new(board, vec![&pattern]);
-
-        match_board.swap(Pos::new(8, 8), Pos::new(6, 6));
+        let match_board = MatchBoard::new(board, vec![&pattern]);
 
-        let next_match = &match_board.match_moves()[0];
-        assert_eq!(Pos::new(6, 6), next_match.missing_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(!next_match.contains(Pos::new(6, 6)));
-        assert_eq!(Pos::new(6, 7), next_match.completing_pos());
+        assert!(match_board.placement_useful(Pos::new(1, 1), TestPiece::Second));
     }
 
     #[test]
-    fn match_moves_swap_self_no_match() {
+    fn best_partial_two_of_three_satisfied() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(4, 4), TestPiece::First);
+        board.set_piece(Pos::new(6, 4), TestPiece::Both);
 
-        board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        board.set_piece(Pos::new(6, 6), TestPiece::Second);
-
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(8, 8)];
-        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
-
-        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
 
-        match_board.swap(Pos::new(6, 6), Pos::new(6, 6));
+        let match_board = MatchBoard::new(board, vec![&pattern]);
 
-        assert!(match_board.match_moves().is_empty())
+        assert_eq!(2, match_board.best_partial(&pattern, Pos::new(5, 4)));
+        assert_eq!(1, match_board.best_partial(&pattern, Pos::new(3, 4)));
+        assert_eq!(0, match_board.best_partial(&pattern, Pos::new(10, 10)));
     }
 
     #[test]
-    fn match_moves_wrong_match_type_none_found() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
+    fn threat_cells_equals_missing_positions() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+        board.set_piece(Pos::new(0, 5), TestPiece::First);
+        board.set_piece(Pos::new(1, 5), TestPiece::First);
+        board.set_piece(Pos::new(2, 6), TestPiece::First);
 
-        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        board.set_piece(Pos::new(10, 10), TestPiece::First);
+        board.set_piece(Pos::new(12, 10), TestPiece::First);
+        board.set_piece(Pos::new(11, 9), TestPiece::First);
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(4, 6), TestPiece::First);
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
 
-        assert!(match_board.match_moves().is_empty())
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+
+        let missing: HashSet<_> = match_board.match_moves().iter()
+            .map(|match_move| match_move.missing_pos())
+            .collect();
+        assert_eq!(missing, match_board.threat_cells());
+        assert!(match_board.threat_cells().contains(&Pos::new(2, 5)));
+        assert!(match_board.threat_cells().contains(&Pos::new(11, 10)));
     }
 
     #[test]
-    fn match_moves_matches_when_not_all_in_queue() {
+    fn last_matches_only_swap_caused_match() {
         let mut board = BoardState::<TestPiece, 15, 16>::new();
-        board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        board.set_piece(Pos::new(2, 1), TestPiece::Second);
+        board.set_piece(Pos::new(0, 0), TestPiece::Second);
+        board.set_piece(Pos::new(1, 0), TestPiece::Second);
+        board.set_piece(Pos::new(2, 0), TestPiece::Second);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
+        board.set_piece(Pos::new(5, 5), TestPiece::Second);
+        board.set_piece(Pos::new(6, 5), TestPiece::Second);
+        board.set_piece(Pos::new(7, 6), TestPiece::Second);
+
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
         let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
 
         let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        assert_eq!(1, match_board.matches().len());
+        assert!(match_board.last_matches().is_empty());
 
-        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+        match_board.swap(Pos::new(7, 6), Pos::new(7, 5));
 
-        let next_match = &match_board.match_moves()[0];
-        assert_eq!(Pos::new(1, 1), next_match.missing_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(!next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(4, 6)));
-        assert_eq!(Pos::new(2, 1), next_match.completing_pos());
+        assert_eq!(2, match_board.matches().len());
+        assert_eq!(1, match_board.last_matches().len());
+        assert!(match_board.last_matches()[0].contains(Pos::new(7, 5)));
+        assert!(!match_board.last_matches()[0].contains(Pos::new(0, 0)));
+
+        match_board.set_piece(Pos::new(10, 10), TestPiece::First);
+        assert!(match_board.last_matches().is_empty());
+        assert_eq!(2, match_board.matches().len());
     }
 
     #[test]
-    fn match_moves_matches_when_changed_twice() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
+    fn placeable_anchors_spanning_and_single_patterns() {
+        let spanning_pos = [Pos::new(0, 0), Pos::new(14, 0), Pos::new(0, 14)];
+        let spanning = MatchPattern::new(TestMatchType::First, &spanning_pos[..]);
+        let single = MatchPattern::new(TestMatchType::First, &[Pos::new(3, 3)]);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+        let match_board: MatchBoard<TestMatchType, TestPiece, 15, 16> = MatchBoard::new(
+            BoardState::new(),
+            vec![&spanning, &single]
+        );
 
-        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        assert_eq!(2, match_board.placeable_anchors(&spanning));
+        assert_eq!(15 * 16, match_board.placeable_anchors(&single));
+    }
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(2, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Both);
+    #[test]
+    fn match_heat_overlapping_matches_shared_cell_two() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
 
-        let next_match = &match_board.match_moves()[0];
-        assert_eq!(Pos::new(1, 1), next_match.missing_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(!next_match.contains(Pos::new(1, 1)));
-        assert!(next_match.contains(Pos::new(4, 6)));
-        assert_eq!(Pos::new(2, 1), next_match.completing_pos());
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
+
+        let match_board = MatchBoard::new(board, vec![&pattern]);
+        assert_eq!(2, match_board.matches().len());
+
+        let heat = match_board.match_heat();
+        assert_eq!(Some(&1), heat.get(&Pos::new(0, 0)));
+        assert_eq!(Some(&2), heat.get(&Pos::new(1, 0)));
+        assert_eq!(Some(&2), heat.get(&Pos::new(2, 0)));
+        assert_eq!(Some(&1), heat.get(&Pos::new(3, 0)));
+        assert_eq!(4, heat.len());
     }
 
     #[test]
-    fn match_moves_never_matches_when_match_overwritten() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
+    fn take_matches_returns_prior_matches_and_empties() {
+        let mut board = BoardState::<TestPiece, 15, 16>::new();
+        board.set_piece(Pos::new(0, 0), TestPiece::First);
+        board.set_piece(Pos::new(1, 0), TestPiece::First);
+        board.set_piece(Pos::new(2, 0), TestPiece::First);
+        board.set_piece(Pos::new(3, 0), TestPiece::First);
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+        let pattern_pos = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(TestMatchType::First, &pattern_pos[..]);
 
         let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        let prior: Vec<OwnedMatch<TestMatchType, 15, 16>> = match_board.matches().iter()
+            .map(OwnedMatch::from)
+            .collect();
+        assert_eq!(2, prior.len());
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        match_board.set_piece(Pos::new(0, 1), TestPiece::First);
+        let taken = match_board.take_matches();
 
-        assert!(match_board.match_moves().is_empty());
+        assert_eq!(prior, taken);
+        assert!(match_board.matches().is_empty());
+        assert_eq!(TestPiece::First, match_board.piece(Pos::new(0, 0)));
     }
 
-    #[test]
-    fn match_moves_set_pieces_matches_earlier_pattern() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
-
-        let pattern_pos1 = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern_pos2 = vec![
-            Pos::new(2, 3), Pos::new(3, 3),
-            Pos::new(6, 8), Pos::new(6, 9)
-        ];
-        let pattern1 = MatchPattern::new(TestMatchType::Second, &pattern_pos1[..]);
-        let pattern2 = MatchPattern::new(TestMatchType::Second, &pattern_pos2[..]);
+    fn zero_size_board_results_empty<const W: usize, const H: usize>() {
+        let pattern = MatchPattern::new(TestMatchType::First, &[]);
+        let run_pattern = RunPattern::new(TestMatchType::First, Orientation::Horizontal, 3);
+        let mut match_board = MatchBoard::with_options(
+            BoardState::<TestPiece, W, H>::new(),
+            vec![&pattern],
+            vec![&run_pattern],
+            true,
+            true
+        );
 
-        let mut match_board = MatchBoard::new(board, vec![&pattern1, &pattern2]);
+        assert!(match_board.matches().is_empty());
+        assert!(match_board.last_matches().is_empty());
+        assert!(match_board.special_matches().is_empty());
+        assert!(match_board.matches_by_pattern().is_empty());
+        assert!(match_board.match_heat().is_empty());
+        assert!(match_board.match_moves().is_empty());
+        assert_eq!(0, match_board.move_count());
+        assert!(match_board.threat_cells().is_empty());
+        assert!(!match_board.has_move_within(2));
+        assert!(match_board.match_breaking_swaps().is_empty());
+        assert!(match_board.classic_matches(3).is_empty());
+        assert!(match_board.clear_preview().is_empty());
+        assert_eq!(0, match_board.placeable_anchors(&pattern));
+        assert!(match_board.is_settled());
+
+        assert!(match_board.remove_matches().is_empty());
+        assert!(match_board.remove_matches_counted().is_empty());
+        assert_eq!(0, match_board.clear_positions([]));
+        assert!(match_board.take_matches().is_empty());
+
+        let result = match_board.step(|_| TestPiece::First);
+        assert!(result.cleared.is_empty());
+        assert!(result.moves.is_empty());
+        assert!(result.spawned.is_empty());
+
+        match_board.map_in_place(|_, _| TestPiece::First);
+        match_board.rescan_all();
+        assert!(match_board.matches().is_empty());
+        assert!(match_board.match_moves().is_empty());
+    }
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::Second);
-        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        match_board.set_piece(Pos::new(4, 7), TestPiece::Both);
+    #[test]
+    fn zero_size_board_zero_by_zero_results_empty() {
+        zero_size_board_results_empty::<0, 0>();
+    }
 
-        let next_match = &match_board.match_moves()[0];
-        assert_eq!(Pos::new(4, 6), next_match.missing_pos());
-        assert!(next_match.contains(Pos::new(0, 1)));
-        assert!(next_match.contains(Pos::new(1, 1)));
-        assert!(!next_match.contains(Pos::new(4, 6)));
-        assert!(!next_match.contains(Pos::new(4, 7)));
+    #[test]
+    fn zero_size_board_zero_width_results_empty() {
+        zero_size_board_results_empty::<0, 5>();
     }
 
     #[test]
-    fn end_game_returns_board() {
-        let board = BoardState::<TestPiece, 15, 16>::new();
+    fn zero_size_board_zero_height_results_empty() {
+        zero_size_board_results_empty::<5, 0>();
+    }
 
-        let pattern_pos = vec![Pos::new(2, 3), Pos::new(3, 3), Pos::new(6, 8)];
-        let pattern = MatchPattern::new(TestMatchType::Second, &pattern_pos[..]);
+    fn thin_board_swaps_and_matches<const W: usize, const H: usize>() {
+        let pattern = MatchPattern::new(TestMatchType::First, &[Pos::new(0, 0)]);
+        let mut match_board = MatchBoard::new(BoardState::<TestPiece, W, H>::new(), vec![&pattern]);
 
-        let mut match_board = MatchBoard::new(board, vec![&pattern]);
+        match_board.set_piece(Pos::new(W - 1, H - 1), TestPiece::First);
+        match_board.swap(Pos::new(0, 0), Pos::new(W - 1, H - 1));
 
-        match_board.set_piece(Pos::new(0, 1), TestPiece::First);
-        match_board.set_piece(Pos::new(1, 1), TestPiece::Both);
-        match_board.set_piece(Pos::new(4, 6), TestPiece::Second);
+        assert_eq!(1, match_board.matches().len());
+        assert_eq!(1, match_board.classic_matches(1).len());
+        assert_eq!(W * H > 1, match_board.move_count() > 0);
+        assert_eq!(1, match_board.step(|_| TestPiece::None).cleared.len());
+    }
 
-        match_board.swap(Pos::new(4, 6), Pos::new(5, 6));
+    #[test]
+    fn thin_board_one_by_one_swaps_and_matches() {
+        thin_board_swaps_and_matches::<1, 1>();
+    }
 
-        let final_board = match_board.end_game();
+    #[test]
+    fn thin_board_one_wide_swaps_and_matches() {
+        thin_board_swaps_and_matches::<1, 5>();
+    }
 
-        for x in 0..15 {
-            for y in 0..16 {
-                if x == 0 && y == 1 {
-                    assert_eq!(TestPiece::First, final_board.piece(Pos::new(x, y)));
-                } else if x == 1 && y == 1 {
-                    assert_eq!(TestPiece::Both, final_board.piece(Pos::new(x, y)));
-                } else if x == 5 && y == 6 {
-                    assert_eq!(TestPiece::Second, final_board.piece(Pos::new(x, y)));
-                } else {
-                    assert_eq!(TestPiece::None, final_board.piece(Pos::new(x, y)));
-                }
-            }
-        }
+    #[test]
+    fn thin_board_one_tall_swaps_and_matches() {
+        thin_board_swaps_and_matches::<5, 1>();
     }
-}
\ No newline at end of file
+}
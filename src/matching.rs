@@ -18,6 +18,7 @@ impl<M: Copy, const W: usize, const H: usize> MatchPattern<M, W, H> {
     /// * `match_type` - match type all pieces must have for this pattern to apply
     /// * `spaces` - unique positions that represents a pattern. The values of the
     ///              positions do not matter: only their relative positions matter.
+    #[allow(clippy::doc_overindented_list_items)]
     pub fn new(match_type: M, spaces: &[Pos<W, H>]) -> MatchPattern<M, W, H> {
         let min_x = spaces.iter().map(|space | space.x()).min().unwrap_or(0);
         let min_y = spaces.iter().map(|space | space.y()).min().unwrap_or(0);
@@ -29,6 +30,15 @@ impl<M: Copy, const W: usize, const H: usize> MatchPattern<M, W, H> {
         MatchPattern { match_type, spaces: spaces_around_origin }
     }
 
+    /// Starts building a new pattern one position at a time. See [`MatchPatternBuilder`].
+    ///
+    /// # Arguments
+    ///
+    /// * `match_type` - match type all pieces must have for this pattern to apply
+    pub fn builder(match_type: M) -> MatchPatternBuilder<M, W, H> {
+        MatchPatternBuilder { match_type, spaces: Vec::new() }
+    }
+
     /// Gets the type of pieces in this pattern.
     pub fn match_type(&self) -> M {
         self.match_type
@@ -39,13 +49,243 @@ impl<M: Copy, const W: usize, const H: usize> MatchPattern<M, W, H> {
         self.spaces.iter()
     }
 
+    /// Returns all of the relative positions in this pattern, sorted in ascending order so
+    /// that the order is the same every time.
+    pub fn sorted_spaces(&self) -> Vec<Pos<W, H>> {
+        let mut spaces: Vec<Pos<W, H>> = self.spaces.iter().copied().collect();
+        spaces.sort();
+        spaces
+    }
+
+    /// Gets the positions this pattern would cover on the board if its relative positions
+    /// were moved to start at `origin`. Returns `None` if any position would be outside
+    /// the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - the new origin to use for the pattern's positions
+    pub fn at_origin(&self, origin: Pos<W, H>) -> Option<HashSet<Pos<W, H>>> {
+        let mut new_positions = HashSet::new();
+
+        for &pos in self.spaces.iter() {
+            if let Ok(new_pos) = pos + origin {
+                new_positions.insert(new_pos);
+            } else {
+                return None;
+            }
+        }
+
+        Some(new_positions)
+    }
+
+    /// Checks whether another pattern has the same match type and the same shape as this
+    /// pattern, allowing the shape to be rotated or reflected.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the pattern to compare with
+    pub fn is_equivalent(&self, other: &MatchPattern<M, W, H>) -> bool where M: PartialEq {
+        if self.match_type != other.match_type || self.spaces.len() != other.spaces.len() {
+            return false;
+        }
+
+        let other_shape = MatchPattern::<M, W, H>::normalize(
+            other.spaces.iter().map(|pos| (pos.x() as isize, pos.y() as isize))
+        );
+
+        type Transform = fn(isize, isize) -> (isize, isize);
+        let transforms: [Transform; 8] = [
+            |x, y| (x, y),
+            |x, y| (-y, x),
+            |x, y| (-x, -y),
+            |x, y| (y, -x),
+            |x, y| (-x, y),
+            |x, y| (x, -y),
+            |x, y| (y, x),
+            |x, y| (-y, -x)
+        ];
+
+        transforms.iter().any(|transform| {
+            let shape = MatchPattern::<M, W, H>::normalize(
+                self.spaces.iter().map(|pos| transform(pos.x() as isize, pos.y() as isize))
+            );
+            shape == other_shape
+        })
+    }
+
+    /// Moves a shape so that its smallest x and y coordinates are zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` - the coordinates of the shape
+    fn normalize(shape: impl Iterator<Item=(isize, isize)>) -> HashSet<(isize, isize)> {
+        let shape: Vec<(isize, isize)> = shape.collect();
+        let min_x = shape.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let min_y = shape.iter().map(|&(_, y)| y).min().unwrap_or(0);
+
+        shape.into_iter().map(|(x, y)| (x - min_x, y - min_y)).collect()
+    }
+
 }
 
-// A match found in a board.
+/// Finds the smallest board width and height that can fit every one of the given patterns.
+///
+/// # Arguments
+///
+/// * `patterns` - the patterns that must fit on the board
+pub fn min_board_size<M, const W: usize, const H: usize>(patterns: &[&MatchPattern<M, W, H>]) -> (usize, usize) {
+    patterns.iter()
+        .flat_map(|pattern| pattern.spaces.iter())
+        .fold((0, 0), |(width, height), pos| (width.max(pos.x() + 1), height.max(pos.y() + 1)))
+}
+
+/// Builds a [`MatchPattern`] by adding its positions one at a time.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MatchPatternBuilder<M, const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+    match_type: M,
+    spaces: Vec<Pos<BOARD_WIDTH, BOARD_HEIGHT>>
+}
+
+impl<M: Copy, const W: usize, const H: usize> MatchPatternBuilder<M, W, H> {
+
+    /// Adds a position to the pattern. Like with [`MatchPattern::new`], only the relative
+    /// positions matter. Adding the same position twice has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x-coordinate of the position to add
+    /// * `y` - y-coordinate of the position to add
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside the board.
+    pub fn add(mut self, x: usize, y: usize) -> MatchPatternBuilder<M, W, H> {
+        self.spaces.push(Pos::new(x, y));
+        self
+    }
+
+    /// Creates the pattern from all the positions added so far.
+    pub fn build(self) -> MatchPattern<M, W, H> {
+        MatchPattern::new(self.match_type, &self.spaces[..])
+    }
+
+}
+
+/// The direction of a straight line of positions on the board.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Orientation {
+
+    /// Along a row, with every position sharing the same y-coordinate
+    Horizontal,
+
+    /// Along a column, with every position sharing the same x-coordinate
+    Vertical
+
+}
+
+/// The direction of the positions in a match, which may not form a straight line.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchOrientation {
+
+    /// Along a row, with every position sharing the same y-coordinate
+    Horizontal,
+
+    /// Along a column, with every position sharing the same x-coordinate
+    Vertical,
+
+    /// Neither along a single row nor a single column
+    Mixed
+
+}
+
+/// A pattern that matches any straight line of at least a minimum number of pieces. Unlike a
+/// [`MatchPattern`], a run pattern produces one match covering the whole line, however long
+/// it is, instead of one match for every fixed-length section of the line.
 #[derive(Clone, Eq, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunPattern<M, const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+    match_type: M,
+    orientation: Orientation,
+    min_len: usize,
+    lines: Vec<MatchPattern<M, BOARD_WIDTH, BOARD_HEIGHT>>
+}
+
+impl<M: Copy, const W: usize, const H: usize> RunPattern<M, W, H> {
+
+    /// Creates a new run pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `match_type` - match type all pieces in the line must have
+    /// * `orientation` - direction of the line
+    /// * `min_len` - fewest pieces in a line that count as a match
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_len` is zero.
+    pub fn new(match_type: M, orientation: Orientation, min_len: usize) -> RunPattern<M, W, H> {
+        assert!(min_len > 0, "run patterns must have a minimum length of at least one");
+
+        let max_len = match orientation {
+            Orientation::Horizontal => W,
+            Orientation::Vertical => H
+        };
+
+        // No line fits on a board without any positions
+        let max_len = match W == 0 || H == 0 {
+            true => 0,
+            false => max_len
+        };
+
+        let lines = (min_len..=max_len).map(|len| {
+            let spaces: Vec<Pos<W, H>> = (0..len).map(|offset| match orientation {
+                Orientation::Horizontal => Pos::new(offset, 0),
+                Orientation::Vertical => Pos::new(0, offset)
+            }).collect();
+
+            MatchPattern::new(match_type, &spaces[..])
+        }).collect();
+
+        RunPattern { match_type, orientation, min_len, lines }
+    }
+
+    /// Gets the type of pieces in this pattern.
+    pub fn match_type(&self) -> M {
+        self.match_type
+    }
+
+    /// Gets the direction of lines that match this pattern.
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Gets the fewest pieces in a line that count as a match.
+    pub fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    /// Gets the fixed pattern for a line of exactly the given length, which is used as the
+    /// pattern of a match found by this run pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - length of the line, which must be at least the minimum length and fit on
+    ///           the board
+    #[allow(clippy::doc_overindented_list_items)]
+    pub(crate) fn line(&self, len: usize) -> &MatchPattern<M, W, H> {
+        &self.lines[len - self.min_len]
+    }
+
+}
+
+// A match found in a board.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Match<'a, M, const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
     pattern: &'a MatchPattern<M, BOARD_WIDTH, BOARD_HEIGHT>,
+    pattern_index: usize,
     changed_pos: Pos<BOARD_WIDTH, BOARD_HEIGHT>,
     board_pos: HashSet<Pos<BOARD_WIDTH, BOARD_HEIGHT>>
 }
@@ -57,10 +297,12 @@ impl<M, const W: usize, const H: usize> Match<'_, M, W, H> {
     /// # Arguments
     ///
     /// * `pattern` - the pattern of the found match
+    /// * `pattern_index` - the index of the pattern in the board's list of patterns
     /// * `changed_pos` - the position that was changed and triggered the match
     /// * `board_pos` - actual positions on the board
-    pub(crate) fn new(pattern: &MatchPattern<M, W, H>, changed_pos: Pos<W, H>, board_pos: HashSet<Pos<W, H>>) -> Match<M, W, H> {
-        Match { pattern, changed_pos, board_pos }
+    pub(crate) fn new(pattern: &MatchPattern<M, W, H>, pattern_index: usize, changed_pos: Pos<W, H>,
+                      board_pos: HashSet<Pos<W, H>>) -> Match<'_, M, W, H> {
+        Match { pattern, pattern_index, changed_pos, board_pos }
     }
 
     /// Gets the pattern associated with this match.
@@ -68,6 +310,13 @@ impl<M, const W: usize, const H: usize> Match<'_, M, W, H> {
         self.pattern
     }
 
+    /// Gets the index of this match's pattern in the list of patterns the board was created
+    /// with. Matches found by a [`RunPattern`] use the number of fixed patterns plus the index
+    /// of the run pattern in the board's list of run patterns.
+    pub fn pattern_index(&self) -> usize {
+        self.pattern_index
+    }
+
     /// Gets the changed position that triggered this match.
     pub fn changed_pos(&self) -> Pos<W, H> {
         self.changed_pos
@@ -87,11 +336,130 @@ impl<M, const W: usize, const H: usize> Match<'_, M, W, H> {
         self.board_pos.iter()
     }
 
+    /// Returns all of the board positions where this pattern is located, sorted in ascending
+    /// order so that the order is the same every time.
+    pub fn sorted_positions(&self) -> Vec<Pos<W, H>> {
+        let mut positions: Vec<Pos<W, H>> = self.board_pos.iter().copied().collect();
+        positions.sort();
+        positions
+    }
+
+    /// Gets the position closest to the average of all positions in this match, rounding
+    /// halfway coordinates up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the match does not contain any positions.
+    pub fn center(&self) -> Pos<W, H> {
+        let count = self.board_pos.len();
+        assert!(count > 0, "cannot find the center of a match with no positions");
+
+        let sum_x: usize = self.board_pos.iter().map(|pos| pos.x()).sum();
+        let sum_y: usize = self.board_pos.iter().map(|pos| pos.y()).sum();
+
+        Pos::new((sum_x + count / 2) / count, (sum_y + count / 2) / count)
+    }
+
+    /// Finds whether every position in this match is in the same row or the same column.
+    /// A match with a single position, or none, is horizontal.
+    pub fn orientation(&self) -> MatchOrientation {
+        match self.extent() {
+            (_, 0) | (_, 1) => MatchOrientation::Horizontal,
+            (1, _) => MatchOrientation::Vertical,
+            _ => MatchOrientation::Mixed
+        }
+    }
+
+    /// Gets the width and height of the smallest rectangle containing every position in this
+    /// match. A match with no positions has an extent of (0, 0).
+    pub fn extent(&self) -> (usize, usize) {
+        let span = |coord: fn(&Pos<W, H>) -> usize| {
+            let min = self.board_pos.iter().map(coord).min();
+            let max = self.board_pos.iter().map(coord).max();
+            min.zip(max).map(|(min, max)| max - min + 1).unwrap_or(0)
+        };
+
+        (span(Pos::x), span(Pos::y))
+    }
+
+    /// Checks if this match and another match share at least one board position.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the match to compare with
+    pub fn intersects(&self, other: &Match<'_, M, W, H>) -> bool {
+        !self.board_pos.is_disjoint(&other.board_pos)
+    }
+
+    /// Gets the board positions that are part of both this match and another match.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the match to compare with
+    pub fn intersection(&self, other: &Match<'_, M, W, H>) -> HashSet<Pos<W, H>> {
+        self.board_pos.intersection(&other.board_pos).copied().collect()
+    }
+
 }
 
-// A group of pieces where one needs to change to make a match.
+/// A match that owns a copy of its pattern, so that it can outlive the board's patterns.
 #[derive(Clone, Eq, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedMatch<M, const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+    pattern: MatchPattern<M, BOARD_WIDTH, BOARD_HEIGHT>,
+    pattern_index: usize,
+    changed_pos: Pos<BOARD_WIDTH, BOARD_HEIGHT>,
+    board_pos: HashSet<Pos<BOARD_WIDTH, BOARD_HEIGHT>>
+}
+
+impl<M, const W: usize, const H: usize> OwnedMatch<M, W, H> {
+
+    /// Gets the pattern associated with this match.
+    pub fn pattern(&self) -> &MatchPattern<M, W, H> {
+        &self.pattern
+    }
+
+    /// Gets the index of this match's pattern in the list of patterns the board was created
+    /// with.
+    pub fn pattern_index(&self) -> usize {
+        self.pattern_index
+    }
+
+    /// Gets the changed position that triggered this match.
+    pub fn changed_pos(&self) -> Pos<W, H> {
+        self.changed_pos
+    }
+
+    /// Checks if the given position on the board is part of the match.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - position to check for in this match
+    pub fn contains(&self, pos: Pos<W, H>) -> bool {
+        self.board_pos.contains(&pos)
+    }
+
+    /// Returns an iterator of all of the board positions where this pattern is located.
+    pub fn iter(&self) -> impl Iterator<Item=&Pos<W, H>> {
+        self.board_pos.iter()
+    }
+
+}
+
+impl<M: Clone, const W: usize, const H: usize> From<&Match<'_, M, W, H>> for OwnedMatch<M, W, H> {
+    fn from(borrowed: &Match<'_, M, W, H>) -> Self {
+        OwnedMatch {
+            pattern: borrowed.pattern.clone(),
+            pattern_index: borrowed.pattern_index,
+            changed_pos: borrowed.changed_pos,
+            board_pos: borrowed.board_pos.clone()
+        }
+    }
+}
+
+// A group of pieces where one needs to change to make a match.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MatchMove<'a, M, const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
     pattern: &'a MatchPattern<M, BOARD_WIDTH, BOARD_HEIGHT>,
     missing_pos: Pos<BOARD_WIDTH, BOARD_HEIGHT>,
@@ -110,8 +478,9 @@ impl<M, const W: usize, const H: usize> MatchMove<'_, M, W, H> {
     /// * `completing_pos` - the position of a piece that could be swapped with the `missing_pos`
     ///                      to create a match
     /// * `board_pos` - actual positions on the board
+    #[allow(clippy::doc_overindented_list_items)]
     pub(crate) fn new(pattern: &MatchPattern<M, W, H>, missing_pos: Pos<W, H>, completing_pos: Pos<W, H>,
-                      board_pos: HashSet<Pos<W, H>>) -> MatchMove<M, W, H> {
+                      board_pos: HashSet<Pos<W, H>>) -> MatchMove<'_, M, W, H> {
         MatchMove { pattern, missing_pos, completing_pos, board_pos }
     }
 
@@ -133,6 +502,73 @@ impl<M, const W: usize, const H: usize> MatchMove<'_, M, W, H> {
         self.completing_pos
     }
 
+    /// Gets the swap that performs this move, as (`completing_pos`, `missing_pos`). Swapping
+    /// these two positions moves the completing piece into the missing position.
+    pub fn as_swap(&self) -> (Pos<W, H>, Pos<W, H>) {
+        (self.completing_pos, self.missing_pos)
+    }
+
+    /// Checks if the given position on the board already contains a piece that would be part of the
+    /// match.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - position to check for in this close match
+    pub fn contains(&self, pos: Pos<W, H>) -> bool {
+        self.board_pos.contains(&pos)
+    }
+
+    /// Returns an iterator of all of the board positions where this pattern is located.
+    /// Does not include the position that is missing.
+    pub fn iter(&self) -> impl Iterator<Item=&Pos<W, H>> {
+        self.board_pos.iter()
+    }
+
+    /// Returns all of the board positions where this pattern is located, sorted in ascending
+    /// order so that the order is the same every time. Does not include the position that is
+    /// missing.
+    pub fn sorted_positions(&self) -> Vec<Pos<W, H>> {
+        let mut positions: Vec<Pos<W, H>> = self.board_pos.iter().copied().collect();
+        positions.sort();
+        positions
+    }
+
+}
+
+/// A match move that owns a copy of its pattern, so that it can outlive the board's patterns
+/// or be sent elsewhere as a hint.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedMatchMove<M, const BOARD_WIDTH: usize, const BOARD_HEIGHT: usize> {
+    pattern: MatchPattern<M, BOARD_WIDTH, BOARD_HEIGHT>,
+    missing_pos: Pos<BOARD_WIDTH, BOARD_HEIGHT>,
+    completing_pos: Pos<BOARD_WIDTH, BOARD_HEIGHT>,
+    board_pos: HashSet<Pos<BOARD_WIDTH, BOARD_HEIGHT>>
+}
+
+impl<M, const W: usize, const H: usize> OwnedMatchMove<M, W, H> {
+
+    /// Gets the pattern associated with this move.
+    pub fn pattern(&self) -> &MatchPattern<M, W, H> {
+        &self.pattern
+    }
+
+    /// Gets the position that needs to be changed to make a match.
+    pub fn missing_pos(&self) -> Pos<W, H> {
+        self.missing_pos
+    }
+
+    /// Gets the position of a piece that can be swapped with the missing position to create
+    /// a match.
+    pub fn completing_pos(&self) -> Pos<W, H> {
+        self.completing_pos
+    }
+
+    /// Gets the swap that performs this move, as (`completing_pos`, `missing_pos`).
+    pub fn as_swap(&self) -> (Pos<W, H>, Pos<W, H>) {
+        (self.completing_pos, self.missing_pos)
+    }
+
     /// Checks if the given position on the board already contains a piece that would be part of the
     /// match.
     ///
@@ -151,11 +587,23 @@ impl<M, const W: usize, const H: usize> MatchMove<'_, M, W, H> {
 
 }
 
+impl<M: Clone, const W: usize, const H: usize> From<&MatchMove<'_, M, W, H>> for OwnedMatchMove<M, W, H> {
+    fn from(borrowed: &MatchMove<'_, M, W, H>) -> Self {
+        OwnedMatchMove {
+            pattern: borrowed.pattern.clone(),
+            missing_pos: borrowed.missing_pos,
+            completing_pos: borrowed.completing_pos,
+            board_pos: borrowed.board_pos.clone()
+        }
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::vec_init_then_push, clippy::map_clone)]
 mod tests {
     use std::collections::HashSet;
-    use crate::MatchMove;
-    use crate::matching::{MatchPattern, Match};
+    use crate::{MatchMove, OwnedMatchMove};
+    use crate::matching::{min_board_size, MatchPattern, Match, MatchOrientation, Orientation, OwnedMatch, RunPattern};
     use crate::position::Pos;
 
     #[test]
@@ -261,6 +709,102 @@ mod tests {
         assert_eq!(0, pattern.match_type());
     }
 
+    #[test]
+    fn builder_same_as_new() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(3, 4), Pos::new(4, 4), Pos::new(4, 5), Pos::new(6, 4)];
+        let pattern = MatchPattern::new(3, &spaces[..]);
+
+        let built_pattern = MatchPattern::builder(3)
+            .add(3, 4)
+            .add(4, 4)
+            .add(4, 5)
+            .add(6, 4)
+            .add(4, 4)
+            .build();
+
+        assert_eq!(pattern, built_pattern);
+    }
+
+    #[test]
+    fn builder_no_positions_empty() {
+        let built_pattern: MatchPattern<u8, 15, 16> = MatchPattern::builder(3).build();
+        assert_eq!(MatchPattern::new(3, &[]), built_pattern);
+        assert_eq!(0, built_pattern.iter().count());
+    }
+
+    #[test]
+    fn is_equivalent_rotated_l_equivalent() {
+        let l_spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2), Pos::new(1, 0)];
+        let l_pattern = MatchPattern::new(0, &l_spaces[..]);
+
+        let rotated_spaces: Vec<Pos<15, 16>> = vec![Pos::new(5, 5), Pos::new(6, 5), Pos::new(7, 5), Pos::new(7, 6)];
+        let rotated_pattern = MatchPattern::new(0, &rotated_spaces[..]);
+
+        let reflected_spaces: Vec<Pos<15, 16>> = vec![Pos::new(1, 0), Pos::new(1, 1), Pos::new(1, 2), Pos::new(0, 0)];
+        let reflected_pattern = MatchPattern::new(0, &reflected_spaces[..]);
+
+        assert!(l_pattern.is_equivalent(&rotated_pattern));
+        assert!(rotated_pattern.is_equivalent(&l_pattern));
+        assert!(l_pattern.is_equivalent(&reflected_pattern));
+        assert!(l_pattern.is_equivalent(&l_pattern));
+    }
+
+    #[test]
+    fn is_equivalent_l_and_t_not_equivalent() {
+        let l_spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2), Pos::new(1, 0)];
+        let l_pattern = MatchPattern::new(0, &l_spaces[..]);
+
+        let t_spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 1), Pos::new(1, 1), Pos::new(2, 1), Pos::new(1, 0)];
+        let t_pattern = MatchPattern::new(0, &t_spaces[..]);
+
+        assert!(!l_pattern.is_equivalent(&t_pattern));
+    }
+
+    #[test]
+    fn is_equivalent_different_type_not_equivalent() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+
+        assert!(!MatchPattern::new(0, &spaces[..]).is_equivalent(&MatchPattern::new(1, &spaces[..])));
+    }
+
+    #[test]
+    fn min_board_size_two_patterns_larger_extents() {
+        let wide_spaces: Vec<Pos<15, 16>> = vec![Pos::new(3, 3), Pos::new(4, 3), Pos::new(5, 3), Pos::new(6, 3)];
+        let wide_pattern = MatchPattern::new(0, &wide_spaces[..]);
+
+        let tall_spaces: Vec<Pos<15, 16>> = vec![Pos::new(1, 1), Pos::new(1, 2), Pos::new(1, 3), Pos::new(2, 2)];
+        let tall_pattern = MatchPattern::new(0, &tall_spaces[..]);
+
+        assert_eq!((4, 3), min_board_size(&[&wide_pattern, &tall_pattern]));
+    }
+
+    #[test]
+    fn min_board_size_no_patterns_zero() {
+        let patterns: [&MatchPattern<u8, 15, 16>; 0] = [];
+        assert_eq!((0, 0), min_board_size(&patterns));
+    }
+
+    #[test]
+    fn at_origin_in_bounds_translated() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2), Pos::new(1, 0)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let expected_positions = HashSet::from([
+            Pos::new(3, 4), Pos::new(3, 5), Pos::new(3, 6), Pos::new(4, 4)
+        ]);
+
+        assert_eq!(Some(expected_positions), pattern.at_origin(Pos::new(3, 4)));
+    }
+
+    #[test]
+    fn at_origin_past_edge_none() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2), Pos::new(1, 0)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        assert!(pattern.at_origin(Pos::new(14, 4)).is_none());
+        assert!(pattern.at_origin(Pos::new(3, 14)).is_none());
+    }
+
     #[test]
     fn new_match_created_with_pattern_has_pattern() {
         let mut spaces: Vec<Pos<15, 16>> = Vec::new();
@@ -275,10 +819,21 @@ mod tests {
         board_pos.insert(Pos::new(6, 0));
         board_pos.insert(Pos::new(10, 5));
 
-        let match1 = Match::new(&pattern, Pos::new(6, 0), board_pos);
+        let match1 = Match::new(&pattern, 0, Pos::new(6, 0), board_pos);
         assert_eq!(pattern, *match1.pattern());
     }
 
+    #[test]
+    fn new_match_created_with_pattern_index_has_index() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 1), Pos::new(1, 0), Pos::new(5, 5)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let board_pos = HashSet::from([Pos::new(5, 1), Pos::new(6, 0), Pos::new(10, 5)]);
+
+        let match1 = Match::new(&pattern, 3, Pos::new(6, 0), board_pos);
+        assert_eq!(3, match1.pattern_index());
+    }
+
     #[test]
     fn new_match_created_with_changed_pos_has_changed_pos() {
         let mut spaces: Vec<Pos<15, 16>> = Vec::new();
@@ -293,7 +848,7 @@ mod tests {
         board_pos.insert(Pos::new(6, 0));
         board_pos.insert(Pos::new(10, 5));
 
-        let match1 = Match::new(&pattern, Pos::new(6, 0), board_pos);
+        let match1 = Match::new(&pattern, 0, Pos::new(6, 0), board_pos);
         assert_eq!(Pos::new(6, 0), match1.changed_pos());
     }
 
@@ -316,7 +871,7 @@ mod tests {
         expected_board_pos.insert(Pos::new(6, 0));
         expected_board_pos.insert(Pos::new(10, 5));
 
-        let match1 = Match::new(&pattern, Pos::new(6, 0), board_pos);
+        let match1 = Match::new(&pattern, 0, Pos::new(6, 0), board_pos);
         assert_eq!(expected_board_pos, match1.iter().map(|&pos| pos).collect());
     }
 
@@ -396,4 +951,203 @@ mod tests {
         let match1 = MatchMove::new(&pattern, Pos::new(6, 0), Pos::new(2, 3), board_pos);
         assert_eq!(Pos::new(2, 3), match1.completing_pos());
     }
+
+    #[test]
+    fn close_match_as_swap_completing_then_missing() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 1), Pos::new(1, 0), Pos::new(5, 5)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let mut board_pos = HashSet::new();
+        board_pos.insert(Pos::new(5, 1));
+        board_pos.insert(Pos::new(10, 5));
+
+        let match1 = MatchMove::new(&pattern, Pos::new(6, 0), Pos::new(6, 1), board_pos);
+        assert_eq!((match1.completing_pos(), match1.missing_pos()), match1.as_swap());
+        assert_eq!((Pos::new(6, 1), Pos::new(6, 0)), match1.as_swap());
+    }
+
+    #[test]
+    fn match_sorted_positions_ascending_order() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 1), Pos::new(1, 0), Pos::new(5, 5)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let board_pos = HashSet::from([Pos::new(10, 5), Pos::new(5, 1), Pos::new(6, 0)]);
+
+        let match1 = Match::new(&pattern, 0, Pos::new(6, 0), board_pos.clone());
+        let match2 = Match::new(&pattern, 0, Pos::new(6, 0), board_pos);
+
+        let expected_positions = vec![Pos::new(5, 1), Pos::new(6, 0), Pos::new(10, 5)];
+        assert_eq!(expected_positions, match1.sorted_positions());
+        assert_eq!(match1.sorted_positions(), match2.sorted_positions());
+    }
+
+    #[test]
+    fn close_match_sorted_positions_ascending_order() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 1), Pos::new(1, 0), Pos::new(5, 5)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let board_pos = HashSet::from([Pos::new(10, 5), Pos::new(5, 1)]);
+
+        let match1 = MatchMove::new(&pattern, Pos::new(6, 0), Pos::new(6, 1), board_pos);
+
+        assert_eq!(vec![Pos::new(5, 1), Pos::new(10, 5)], match1.sorted_positions());
+    }
+
+    #[test]
+    fn match_intersection_overlapping_shared_positions() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let match1 = Match::new(&pattern, 0, Pos::new(2, 0), HashSet::from([Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)]));
+        let match2 = Match::new(&pattern, 0, Pos::new(2, 0), HashSet::from([Pos::new(2, 0), Pos::new(2, 1), Pos::new(2, 2)]));
+
+        assert!(match1.intersects(&match2));
+        assert!(match2.intersects(&match1));
+        assert_eq!(HashSet::from([Pos::new(2, 0)]), match1.intersection(&match2));
+    }
+
+    #[test]
+    fn match_intersection_disjoint_empty() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let match1 = Match::new(&pattern, 0, Pos::new(2, 0), HashSet::from([Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)]));
+        let match2 = Match::new(&pattern, 0, Pos::new(3, 1), HashSet::from([Pos::new(3, 1), Pos::new(4, 1), Pos::new(5, 1)]));
+
+        assert!(!match1.intersects(&match2));
+        assert!(match1.intersection(&match2).is_empty());
+    }
+
+    #[test]
+    fn match_center_horizontal_three_middle() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let match1 = Match::new(&pattern, 0, Pos::new(5, 4), HashSet::from([Pos::new(4, 4), Pos::new(5, 4), Pos::new(6, 4)]));
+
+        assert_eq!(Pos::new(5, 4), match1.center());
+        assert_eq!((3, 1), match1.extent());
+    }
+
+    #[test]
+    fn match_center_l_shape_rounded() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(0, 1), Pos::new(0, 2)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let match1 = Match::new(&pattern, 0, Pos::new(2, 2), HashSet::from([
+            Pos::new(2, 2), Pos::new(3, 2), Pos::new(2, 3), Pos::new(2, 4)
+        ]));
+
+        assert_eq!(Pos::new(2, 3), match1.center());
+        assert_eq!((2, 3), match1.extent());
+    }
+
+    #[test]
+    #[should_panic]
+    fn match_center_empty_panics() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let match1 = Match::new(&pattern, 0, Pos::new(2, 2), HashSet::new());
+        assert_eq!((0, 0), match1.extent());
+
+        match1.center();
+    }
+
+    #[test]
+    fn owned_match_from_match_same_contents() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let board_pos = HashSet::from([Pos::new(4, 4), Pos::new(5, 4), Pos::new(6, 4)]);
+        let match1 = Match::new(&pattern, 2, Pos::new(5, 4), board_pos);
+
+        let owned_match = OwnedMatch::from(&match1);
+        drop(match1);
+
+        assert_eq!(pattern, *owned_match.pattern());
+        assert_eq!(2, owned_match.pattern_index());
+        assert_eq!(Pos::new(5, 4), owned_match.changed_pos());
+        assert!(owned_match.contains(Pos::new(6, 4)));
+        assert_eq!(3, owned_match.iter().count());
+    }
+
+    #[test]
+    fn run_pattern_line_has_requested_length() {
+        let pattern: RunPattern<u8, 15, 16> = RunPattern::new(3, Orientation::Vertical, 3);
+
+        let line = pattern.line(5);
+        let expected: HashSet<Pos<15, 16>> = (0..5).map(|y| Pos::new(0, y)).collect();
+        assert_eq!(expected, line.iter().copied().collect());
+        assert_eq!(3, line.match_type());
+        assert_eq!(16 - 3 + 1, pattern.lines.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn run_pattern_zero_min_len_panics() {
+        RunPattern::<u8, 15, 16>::new(3, Orientation::Horizontal, 0);
+    }
+
+    #[test]
+    fn match_orientation_lines_and_l() {
+        let spaces: Vec<Pos<15, 16>> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let pattern = MatchPattern::new(0, &spaces[..]);
+
+        let horizontal = Match::new(&pattern, 0, Pos::new(4, 4), HashSet::from([
+            Pos::new(4, 4), Pos::new(5, 4), Pos::new(6, 4)
+        ]));
+        let vertical = Match::new(&pattern, 0, Pos::new(4, 4), HashSet::from([
+            Pos::new(4, 4), Pos::new(4, 5), Pos::new(4, 6)
+        ]));
+        let l_shape = Match::new(&pattern, 0, Pos::new(4, 4), HashSet::from([
+            Pos::new(4, 4), Pos::new(5, 4), Pos::new(4, 5)
+        ]));
+
+        assert_eq!(MatchOrientation::Horizontal, horizontal.orientation());
+        assert_eq!(MatchOrientation::Vertical, vertical.orientation());
+        assert_eq!(MatchOrientation::Mixed, l_shape.orientation());
+    }
+
+    #[test]
+    fn sorted_spaces_three_cell_pattern_stable_order() {
+        let pattern = MatchPattern::<u8, 16, 16>::new(
+            0,
+            &[Pos::new(5, 4), Pos::new(3, 5), Pos::new(3, 4)]
+        );
+
+        let expected = vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(2, 0)];
+        assert_eq!(expected, pattern.sorted_spaces());
+        assert_eq!(pattern.sorted_spaces(), pattern.clone().sorted_spaces());
+    }
+
+    #[test]
+    fn owned_match_move_from_borrowed_same_data() {
+        let pattern = MatchPattern::<u8, 15, 16>::new(1, &[Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)]);
+        let board_pos: HashSet<Pos<15, 16>> = [Pos::new(3, 4), Pos::new(5, 4)].into_iter().collect();
+        let match_move = MatchMove::new(&pattern, Pos::new(4, 4), Pos::new(4, 5), board_pos);
+
+        let owned = OwnedMatchMove::from(&match_move);
+
+        assert_eq!(&pattern, owned.pattern());
+        assert_eq!(Pos::new(4, 4), owned.missing_pos());
+        assert_eq!(Pos::new(4, 5), owned.completing_pos());
+        assert_eq!(match_move.as_swap(), owned.as_swap());
+        assert!(owned.contains(Pos::new(3, 4)));
+        assert!(!owned.contains(Pos::new(4, 4)));
+        assert_eq!(2, owned.iter().count());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn owned_match_move_serde_round_trip_equal() {
+        let pattern = MatchPattern::<u8, 15, 16>::new(1, &[Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)]);
+        let board_pos: HashSet<Pos<15, 16>> = [Pos::new(3, 4), Pos::new(5, 4)].into_iter().collect();
+        let owned = OwnedMatchMove::from(&MatchMove::new(&pattern, Pos::new(4, 4), Pos::new(4, 5), board_pos));
+
+        let serialized = serde_json::to_string(&owned).unwrap();
+        let deserialized: OwnedMatchMove<u8, 15, 16> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(owned, deserialized);
+    }
 }
@@ -49,6 +49,43 @@ impl<const W: usize> Col<W> {
         Ok(Col { x })
     }
 
+    /// Returns an iterator of every column on the board, from left to right.
+    pub fn all() -> impl Iterator<Item=Col<W>> {
+        (0..W).map(|x| Col { x })
+    }
+
+    /// Gets the column a certain distance to the right of this column, or to the left if the
+    /// distance is negative. Returns `None` if that column would be outside the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `d` - the number of columns to move right
+    pub fn offset(&self, d: isize) -> Option<Col<W>> {
+        self.x.checked_add_signed(d)
+            .and_then(|x| Col::try_new(x).ok())
+    }
+
+}
+
+/// One of the four directions on the board. North points toward larger y values and
+/// east points toward larger x values.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West
+}
+
+/// A line that divides the board in half. The horizontal axis runs from left to right
+/// through the middle row, and the vertical axis runs from bottom to top through the middle
+/// column.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Axis {
+    Horizontal,
+    Vertical
 }
 
 /// Defines errors possible from [`Pos`] methods.
@@ -120,6 +157,37 @@ impl<const W: usize, const H: usize> Pos<W, H> {
         self.y
     }
 
+    /// Checks whether this position is in the leftmost or rightmost column or the bottom or
+    /// top row of the board.
+    pub fn is_edge(&self) -> bool {
+        self.x == 0 || self.x == W - 1 || self.y == 0 || self.y == H - 1
+    }
+
+    /// Checks whether this position is in one of the four corners of the board.
+    pub fn is_corner(&self) -> bool {
+        (self.x == 0 || self.x == W - 1) && (self.y == 0 || self.y == H - 1)
+    }
+
+    /// Returns the position directly adjacent to this one in the given direction, or an error
+    /// if that position would be outside the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - the direction of the adjacent position
+    pub fn adjacent(&self, dir: Direction) -> Result<Pos<W, H>, PosError> {
+        let (new_x, new_y) = match dir {
+            Direction::North => (Some(self.x), self.y.checked_add(1)),
+            Direction::South => (Some(self.x), self.y.checked_sub(1)),
+            Direction::East => (self.x.checked_add(1), Some(self.y)),
+            Direction::West => (self.x.checked_sub(1), Some(self.y))
+        };
+
+        match (new_x, new_y) {
+            (Some(x), Some(y)) => Pos::try_new(x, y),
+            _ => Err(PosError::Overflow)
+        }
+    }
+
 }
 
 impl<const MX: usize, const MY: usize> Add for Pos<MX, MY> {
@@ -166,7 +234,7 @@ impl<const MX: usize, const MY: usize> Display for Pos<MX, MY> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Col, ColError, PosError};
+    use crate::{Col, ColError, Direction, PosError};
     use crate::position::Pos;
 
     #[test]
@@ -214,6 +282,40 @@ mod tests {
         assert_eq!(1, col.x);
     }
 
+    #[test]
+    fn col_all_yields_every_column() {
+        let cols: Vec<Col<15>> = Col::all().collect();
+
+        assert_eq!(15, cols.len());
+        for (x, col) in cols.into_iter().enumerate() {
+            assert_eq!(x, col.x);
+        }
+    }
+
+    #[test]
+    fn col_all_zero_width_empty() {
+        assert_eq!(0, Col::<0>::all().count());
+    }
+
+    #[test]
+    fn col_offset_in_bounds() {
+        let col: Col<15> = Col::new(5);
+
+        assert_eq!(Some(Col::new(8)), col.offset(3));
+        assert_eq!(Some(Col::new(0)), col.offset(-5));
+        assert_eq!(Some(col), col.offset(0));
+    }
+
+    #[test]
+    fn col_offset_past_edge_none() {
+        let col: Col<15> = Col::new(5);
+
+        assert_eq!(None, col.offset(10));
+        assert_eq!(None, col.offset(-6));
+        assert_eq!(None, col.offset(isize::MAX));
+        assert_eq!(None, col.offset(isize::MIN));
+    }
+
     #[test]
     #[should_panic]
     fn pos_new_x_out_of_bounds() {
@@ -294,6 +396,29 @@ mod tests {
         assert_eq!(4, pos.y());
     }
 
+    #[test]
+    fn adjacent_all_directions_in_bounds() {
+        let pos: Pos<15, 16> = Pos::new(1, 4);
+        assert_eq!(Ok(Pos::new(1, 5)), pos.adjacent(Direction::North));
+        assert_eq!(Ok(Pos::new(1, 3)), pos.adjacent(Direction::South));
+        assert_eq!(Ok(Pos::new(2, 4)), pos.adjacent(Direction::East));
+        assert_eq!(Ok(Pos::new(0, 4)), pos.adjacent(Direction::West));
+    }
+
+    #[test]
+    fn adjacent_at_origin_overflow() {
+        let pos: Pos<15, 16> = Pos::new(0, 0);
+        assert_eq!(Err(PosError::Overflow), pos.adjacent(Direction::South));
+        assert_eq!(Err(PosError::Overflow), pos.adjacent(Direction::West));
+    }
+
+    #[test]
+    fn adjacent_at_far_corner_out_of_bounds() {
+        let pos: Pos<15, 16> = Pos::new(14, 15);
+        assert_eq!(Err(PosError::OutOfBounds(14, 16)), pos.adjacent(Direction::North));
+        assert_eq!(Err(PosError::OutOfBounds(15, 15)), pos.adjacent(Direction::East));
+    }
+
     #[test]
     fn add_positive_components_summed() {
         let pos1: Pos<15, 16> = Pos::new(1, 4);
@@ -359,4 +484,31 @@ mod tests {
         let pos: Pos<15, 16> = Pos::new(1, 4);
         assert_eq!("(1, 4)", format!("{}", pos));
     }
+
+    #[test]
+    fn is_edge_is_corner_corners() {
+        for (x, y) in [(0, 0), (14, 0), (0, 15), (14, 15)] {
+            let pos: Pos<15, 16> = Pos::new(x, y);
+            assert!(pos.is_edge());
+            assert!(pos.is_corner());
+        }
+    }
+
+    #[test]
+    fn is_edge_is_corner_edges() {
+        for (x, y) in [(0, 7), (14, 3), (6, 0), (9, 15)] {
+            let pos: Pos<15, 16> = Pos::new(x, y);
+            assert!(pos.is_edge());
+            assert!(!pos.is_corner());
+        }
+    }
+
+    #[test]
+    fn is_edge_is_corner_interior() {
+        for (x, y) in [(1, 1), (13, 14), (7, 8)] {
+            let pos: Pos<15, 16> = Pos::new(x, y);
+            assert!(!pos.is_edge());
+            assert!(!pos.is_corner());
+        }
+    }
 }